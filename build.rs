@@ -1,8 +1,16 @@
+use std::env;
 use std::fs;
 use std::path::Path;
 use std::process::Command;
 
+/// sled version the stored data format was last verified against
+const TESTED_SLED_VERSION: &str = "0.34.7";
+/// bincode version the stored data format was last verified against
+const TESTED_BINCODE_VERSION: &str = "2.0.1";
+
 fn main() {
+    check_storage_versions();
+
     println!("cargo:rerun-if-changed=crates/todoapp-frontend/src");
     println!("cargo:rerun-if-changed=crates/todoapp-frontend/assets");
     println!("cargo:rerun-if-changed=crates/todoapp-frontend/Dioxus.toml");
@@ -58,6 +66,69 @@ fn main() {
     }
 }
 
+/// Stored todos are bincode records in sled, so an upgrade of either crate can
+/// make existing databases unreadable. Warns when the resolved versions differ
+/// from the tested ones and fails the build on a breaking (semver) change.
+fn check_storage_versions() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-env-changed=TODOAPP_SKIP_VERSION_CHECK");
+    if env::var("TODOAPP_SKIP_VERSION_CHECK").is_ok_and(|value| value == "1") {
+        return;
+    }
+
+    let Ok(lock) = fs::read_to_string("Cargo.lock") else {
+        println!("cargo:warning=Cargo.lock not found, skipping sled/bincode version check");
+        return;
+    };
+    for (name, tested) in [
+        ("sled", TESTED_SLED_VERSION),
+        ("bincode", TESTED_BINCODE_VERSION),
+    ] {
+        let Some(resolved) = locked_version(&lock, name) else {
+            println!("cargo:warning={} not found in Cargo.lock", name);
+            continue;
+        };
+        if resolved == tested {
+            continue;
+        }
+        println!(
+            "cargo:warning={} {} is in use but the storage format was tested with {}",
+            name, resolved, tested
+        );
+        if breaking_part(&resolved) != breaking_part(tested) {
+            panic!(
+                "Breaking version change detected: {} {} -> {}. Add a data migration and \
+                 update TESTED_{}_VERSION in build.rs, or set TODOAPP_SKIP_VERSION_CHECK=1.",
+                name,
+                tested,
+                resolved,
+                name.to_uppercase()
+            );
+        }
+    }
+}
+
+/// Version of the package called `name` in a Cargo.lock file
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    lines.find(|line| *line == name_line)?;
+    let version = lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')?;
+    Some(version.to_string())
+}
+
+/// The part of a version that semver allows to break compatibility: the
+/// major version, or the minor version while the major is 0
+fn breaking_part(version: &str) -> (&str, Option<&str>) {
+    let mut parts = version.split('.');
+    let major = parts.next().unwrap_or(version);
+    let minor = (major == "0").then(|| parts.next()).flatten();
+    (major, minor)
+}
+
 fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Result<()> {
     fs::create_dir_all(&dst)?;
     for entry in fs::read_dir(src)? {
@@ -0,0 +1,87 @@
+//! Draws the open todo count as a badge over the favicon.
+
+use tracing::error;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, HtmlLinkElement};
+
+const SIZE: u32 = 32;
+
+/// Replaces the page icon with the SVG `base` plus a count badge, or just
+/// `base` when `count` is zero.
+pub fn draw_badge(base: &str, count: usize) {
+    if let Err(e) = try_draw_badge(base, count) {
+        error!(error = ?e, "Failed to draw favicon badge");
+    }
+}
+
+fn try_draw_badge(base: &str, count: usize) -> Result<(), JsValue> {
+    let link = icon_link()?;
+    if count == 0 {
+        link.set_type("image/svg+xml");
+        link.set_href(base);
+        return Ok(());
+    }
+
+    // The base image has to finish loading before it can be drawn onto a canvas
+    let image = HtmlImageElement::new()?;
+    let loaded = image.clone();
+    let onload = Closure::once_into_js(move || {
+        if let Err(e) = render(&loaded, &link, count) {
+            error!(error = ?e, "Failed to render favicon badge");
+        }
+    });
+    image.set_onload(Some(onload.unchecked_ref()));
+    image.set_src(base);
+    Ok(())
+}
+
+fn render(image: &HtmlImageElement, link: &HtmlLinkElement, count: usize) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("document unavailable")?;
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(SIZE);
+    canvas.set_height(SIZE);
+    let ctx = canvas
+        .get_context("2d")?
+        .ok_or("2d context unavailable")?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    let size = SIZE as f64;
+    ctx.draw_image_with_html_image_element_and_dw_and_dh(image, 0.0, 0.0, size, size)?;
+
+    let radius = size * 0.3;
+    let (x, y) = (size - radius, size - radius);
+    ctx.begin_path();
+    ctx.arc(x, y, radius, 0.0, std::f64::consts::TAU)?;
+    ctx.set_fill_style_str("#ef4444");
+    ctx.fill();
+
+    let label = if count > 9 {
+        "9+".to_string()
+    } else {
+        count.to_string()
+    };
+    ctx.set_fill_style_str("#ffffff");
+    ctx.set_font("bold 14px sans-serif");
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("middle");
+    ctx.fill_text(&label, x, y)?;
+
+    // The canvas is exported as PNG, so the link's type has to follow
+    link.set_type("image/png");
+    link.set_href(&canvas.to_data_url()?);
+    Ok(())
+}
+
+fn icon_link() -> Result<HtmlLinkElement, JsValue> {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("document unavailable")?
+        .query_selector("link[rel='icon']")?
+        .ok_or("icon link not found")?
+        .dyn_into::<HtmlLinkElement>()
+        .map_err(JsValue::from)
+}
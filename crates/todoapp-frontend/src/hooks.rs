@@ -0,0 +1,153 @@
+//! Reusable hooks shared by the components.
+
+use std::future::Future;
+use std::time::Duration;
+
+use dioxus::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::warn;
+
+/// Progress of a value that's being fetched asynchronously
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsyncState<T> {
+    Loading,
+    Ready(T),
+    Error(String),
+}
+
+impl<T> AsyncState<T> {
+    /// The fetched value, if it has arrived
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            AsyncState::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Runs `f` once when the component mounts and tracks its result.
+///
+/// The returned signal can be written to directly, e.g. to replace the value
+/// after a refresh without going back to `Loading`.
+pub fn use_async<T, F, Fut>(f: F) -> Signal<AsyncState<T>>
+where
+    T: 'static,
+    F: FnOnce() -> Fut + 'static,
+    Fut: Future<Output = Result<T, String>> + 'static,
+{
+    let mut state = use_signal(|| AsyncState::Loading);
+    use_hook(move || {
+        spawn(async move {
+            match f().await {
+                Ok(value) => state.set(AsyncState::Ready(value)),
+                Err(e) => state.set(AsyncState::Error(e)),
+            }
+        });
+    });
+    state
+}
+
+/// Calls `f` every `period` until the component unmounts
+pub fn use_interval(period: Duration, f: impl Fn() + 'static) {
+    let task = use_hook(move || {
+        spawn(async move {
+            loop {
+                sleep(period).await;
+                f();
+            }
+        })
+    });
+    use_drop(move || task.cancel());
+}
+
+/// Returns a function that calls `f` with its latest argument once it has
+/// not been called for `delay`
+pub fn use_debounce<T: 'static>(
+    delay: Duration,
+    f: impl Fn(T) + Copy + 'static,
+) -> impl Fn(T) + Copy {
+    let pending = use_signal(|| None::<Task>);
+    move |value: T| {
+        let mut pending = pending;
+        if let Some(task) = pending.write().take() {
+            task.cancel();
+        }
+        pending.set(Some(spawn(async move {
+            sleep(delay).await;
+            f(value);
+        })));
+    }
+}
+
+/// Keeps a value in `localStorage` under `key`, stored as JSON.
+///
+/// Returns the current value and a setter that updates both the signal and
+/// the stored copy. Missing or unreadable entries fall back to `default`.
+pub fn use_local_storage<T>(key: &'static str, default: T) -> (Signal<T>, impl Fn(T) + Copy)
+where
+    T: Serialize + DeserializeOwned + 'static,
+{
+    let value = use_signal(move || load(key).unwrap_or(default));
+    let set = move |new: T| {
+        store(key, &new);
+        let mut value = value;
+        value.set(new);
+    };
+    (value, set)
+}
+
+fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let raw = read_storage(key)?;
+    match serde_json::from_str(&raw) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!(key, error = %e, "Ignoring invalid value in localStorage");
+            None
+        }
+    }
+}
+
+fn store<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(raw) => write_storage(key, &raw),
+        Err(e) => warn!(key, error = %e, "Failed to serialize value for localStorage"),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_storage(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_storage(key: &str, raw: &str) {
+    if let Some(storage) = local_storage() {
+        if let Err(e) = storage.set_item(key, raw) {
+            warn!(key, error = ?e, "Failed to write to localStorage");
+        }
+    }
+}
+
+// There's no localStorage outside the browser, so values only live in memory
+#[cfg(not(target_arch = "wasm32"))]
+fn read_storage(_key: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_storage(_key: &str, _raw: &str) {}
+
+#[cfg(target_arch = "wasm32")]
+async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
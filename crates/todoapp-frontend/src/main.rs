@@ -1,15 +1,33 @@
 use dioxus::prelude::*;
-use todoapp_transfer::{CreateTodoRequest, Priority, TodoResponse, UpdateTodoRequest};
+use hooks::{use_async, use_debounce, use_interval, use_local_storage, AsyncState};
+use std::time::Duration;
+use tag_input::{TagInput, TagList};
+use todoapp_transfer::{
+    CreateTodoRequest, DeleteCompletedResponse, DueDateRequest, Granularity, PinRequest, Priority,
+    RelatedTodoResponse, SearchQuery, StatsResponse, TimelineBucket, TimelineQuery, TodoResponse,
+    UpdateTodoRequest,
+};
 use tracing::{error, info};
 #[cfg(not(target_arch = "wasm32"))]
 use tracing_subscriber::EnvFilter;
+use virtual_list::VirtualList;
+
+#[cfg(target_arch = "wasm32")]
+mod favicon;
+mod hooks;
+mod tag_input;
+mod virtual_list;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
+const FAVICON_SVG: Asset = asset!("/assets/favicon.svg");
 const MAIN_CSS: Asset = asset!("/assets/main.css");
 const TAILWIND_CSS: Asset = asset!("/assets/tailwind.css");
 
 const API_BASE: &str = "http://localhost:3000/api";
 
+/// Lists longer than this only render the rows in view
+const VIRTUAL_LIST_THRESHOLD: usize = 100;
+
 fn main() {
     init_tracing();
     dioxus::launch(App);
@@ -37,28 +55,104 @@ fn init_tracing() {
 
 #[component]
 fn App() -> Element {
-    let mut todos = use_signal(|| Vec::<TodoResponse>::new());
-    let mut loading = use_signal(|| true);
-    let mut error_msg = use_signal(|| Option::<String>::None);
+    let mut todos = use_async(fetch_todos);
 
-    // Load todos on mount
-    use_effect(move || {
+    let active_count = use_memo(move || {
+        todos
+            .read()
+            .value()
+            .map_or(0, |todos| todos.iter().filter(|t| !t.completed).count())
+    });
+    let pinned_todos = use_memo(move || {
+        todos
+            .read()
+            .value()
+            .into_iter()
+            .flatten()
+            .filter(|t| t.pinned)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    let unpinned_todos = use_memo(move || {
+        todos
+            .read()
+            .value()
+            .into_iter()
+            .flatten()
+            .filter(|t| !t.pinned)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+
+    let pinned_count = pinned_todos.read().len();
+    let loading = matches!(*todos.read(), AsyncState::Loading);
+    let load_error = match &*todos.read() {
+        AsyncState::Error(e) => Some(e.clone()),
+        _ => None,
+    };
+
+    let mut search = use_signal(String::new);
+
+    // Refreshes keep showing search results while there's search text
+    let refresh_todos = move |_: ()| {
         spawn(async move {
-            match fetch_todos().await {
-                Ok(fetched_todos) => {
-                    todos.set(fetched_todos);
-                    loading.set(false);
-                }
-                Err(e) => {
-                    error!(error = %e, "Failed to load todos");
-                    error_msg.set(Some(format!("Failed to load todos: {}", e)));
-                    loading.set(false);
+            let query = search.peek().trim().to_string();
+            let fetched = if query.is_empty() {
+                fetch_todos().await
+            } else {
+                search_todos(&query).await
+            };
+            if let Ok(fetched_todos) = fetched {
+                todos.set(AsyncState::Ready(fetched_todos));
+            }
+        });
+    };
+    let search_later = use_debounce(Duration::from_millis(300), refresh_todos);
+
+    let surprise_me = move |_| {
+        spawn(async move {
+            match fetch_random_todo().await {
+                Ok(Some(todo)) => highlight_todo(todo.id),
+                Ok(None) => info!("No incomplete todos to pick from"),
+                Err(e) => error!(error = %e, "Failed to pick a random todo"),
+            }
+        });
+    };
+
+    let clear_completed = move |_| {
+        spawn(async move {
+            match delete_completed_todos().await {
+                Ok(deleted) => {
+                    info!(deleted, "Cleared completed todos");
+                    refresh_todos(());
                 }
+                Err(e) => error!(error = %e, "Failed to clear completed todos"),
             }
         });
+    };
+
+    // Pick up changes made elsewhere, e.g. in another tab
+    use_interval(Duration::from_secs(60), move || refresh_todos(()));
+
+    // Mirror the number of open todos in the tab title, like an unread count
+    let page_title = use_memo(move || {
+        let active = active_count();
+        if active == 0 {
+            "Todo App".to_string()
+        } else {
+            format!("({}) Todo App", active)
+        }
     });
 
+    // ...and as a badge on the favicon
+    #[cfg(target_arch = "wasm32")]
+    use_effect(move || favicon::draw_badge(&FAVICON_SVG.to_string(), active_count()));
+
     rsx! {
+        document::Title { "{page_title}" }
+        document::Meta { name: "theme-color", content: "#3b82f6" }
+        // The SVG comes first so the favicon badge draws onto it; the .ico is a fallback
+        document::Link { rel: "icon", r#type: "image/svg+xml", href: FAVICON_SVG }
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: MAIN_CSS }
         document::Link { rel: "stylesheet", href: TAILWIND_CSS }
@@ -75,36 +169,62 @@ fn App() -> Element {
                     }
                 }
 
-                if let Some(err) = error_msg() {
+                StatsPanel {}
+
+                div { class: "flex justify-end gap-3 mb-6",
+                    input {
+                        r#type: "search",
+                        class: "flex-1 px-4 py-2 border border-gray-200 rounded-lg shadow focus:outline-none focus:border-blue-500",
+                        value: "{search}",
+                        oninput: move |e| {
+                            search.set(e.value());
+                            search_later(());
+                        },
+                        placeholder: "🔍 Search todos"
+                    }
+                    button {
+                        class: "bg-white text-gray-700 font-semibold px-4 py-2 rounded-lg shadow hover:bg-gray-50 border border-gray-200",
+                        onclick: clear_completed,
+                        "🧹 Clear completed"
+                    }
+                    button {
+                        class: "bg-white text-gray-700 font-semibold px-4 py-2 rounded-lg shadow hover:bg-gray-50 border border-gray-200",
+                        onclick: surprise_me,
+                        "🎲 Surprise me!"
+                    }
+                }
+
+                if let Some(err) = load_error {
                     div { class: "bg-red-50 border-l-4 border-red-500 text-red-700 p-4 rounded-lg mb-6 shadow",
-                        "⚠️ {err}"
+                        "⚠️ Failed to load todos: {err}"
                     }
                 }
 
-                AddTodoForm {
-                    on_todo_added: move |_| {
-                        spawn(async move {
-                            if let Ok(fetched_todos) = fetch_todos().await {
-                                todos.set(fetched_todos);
-                            }
-                        });
+                if !pinned_todos().is_empty() {
+                    div { class: "mb-8",
+                        h2 { class: "text-xl font-bold text-gray-800 mb-4",
+                            "📌 Pinned ({pinned_count})"
+                        }
+                        TodoList {
+                            todos: pinned_todos(),
+                            on_todo_changed: refresh_todos
+                        }
                     }
                 }
 
-                if loading() {
+                AddTodoForm {
+                    on_todo_added: refresh_todos
+                }
+
+                if loading {
                     div { class: "text-center py-8",
                         p { class: "text-gray-600", "Loading..." }
                     }
-                } else {
+                } else if pinned_todos().is_empty() || !unpinned_todos().is_empty() {
+                    // Skip the empty-state card when every todo is pinned
                     TodoList {
-                        todos: todos(),
-                        on_todo_changed: move |_| {
-                            spawn(async move {
-                                if let Ok(fetched_todos) = fetch_todos().await {
-                                    todos.set(fetched_todos);
-                                }
-                            });
-                        }
+                        todos: unpinned_todos(),
+                        on_todo_changed: refresh_todos
                     }
                 }
             }
@@ -117,6 +237,8 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
     let mut title = use_signal(|| String::new());
     let mut description = use_signal(|| String::new());
     let mut priority = use_signal(|| Priority::Medium);
+    let mut estimate = use_signal(|| String::new());
+    let mut tags = use_signal(Vec::<String>::new);
     let mut submitting = use_signal(|| false);
 
     let on_submit = move |_| {
@@ -131,6 +253,8 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
             Some(description())
         };
         let todo_priority = priority();
+        let todo_estimate = estimate().trim().parse::<f32>().ok();
+        let todo_tags = tags();
 
         submitting.set(true);
 
@@ -140,6 +264,10 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
                 description: todo_desc,
                 due_date: None,
                 priority: todo_priority,
+                metadata: None,
+                estimate_hours: todo_estimate,
+                tags: (!todo_tags.is_empty()).then_some(todo_tags),
+                created_at: None,
             };
 
             match create_todo(req).await {
@@ -147,6 +275,8 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
                     title.set(String::new());
                     description.set(String::new());
                     priority.set(Priority::Medium);
+                    estimate.set(String::new());
+                    tags.set(Vec::new());
                     on_todo_added.call(());
                 }
                 Err(e) => {
@@ -190,6 +320,24 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
                     }
                 }
 
+                div { class: "mb-5",
+                    label { class: "block text-sm font-semibold text-gray-700 mb-2", "Estimate in hours (optional)" }
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        step: "0.5",
+                        class: "w-full px-4 py-3 border-2 border-gray-200 rounded-xl focus:outline-none focus:border-blue-500 focus:ring-4 focus:ring-blue-50 transition-all",
+                        value: "{estimate}",
+                        oninput: move |e| estimate.set(e.value()),
+                        placeholder: "e.g. 1.5"
+                    }
+                }
+
+                div { class: "mb-5",
+                    label { class: "block text-sm font-semibold text-gray-700 mb-2", "Tags (optional)" }
+                    TagInput { tags }
+                }
+
                 div { class: "mb-6",
                     label { class: "block text-sm font-semibold text-gray-700 mb-2", "Priority Level" }
                     select {
@@ -198,6 +346,8 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
                             let p = match e.value().as_str() {
                                 "Low" => Priority::Low,
                                 "High" => Priority::High,
+                                "VeryHigh" => Priority::VeryHigh,
+                                "Critical" => Priority::Critical,
                                 _ => Priority::Medium,
                             };
                             priority.set(p);
@@ -205,6 +355,8 @@ fn AddTodoForm(on_todo_added: EventHandler<()>) -> Element {
                         option { value: "Low", "🟢 Low Priority" }
                         option { value: "Medium", selected: true, "🟡 Medium Priority" }
                         option { value: "High", "🔴 High Priority" }
+                        option { value: "VeryHigh", "🟠 Very High Priority" }
+                        option { value: "Critical", "🚨 Critical Priority" }
                     }
                 }
 
@@ -235,6 +387,12 @@ fn TodoList(todos: Vec<TodoResponse>, on_todo_changed: EventHandler<()>) -> Elem
         };
     }
 
+    if todos.len() > VIRTUAL_LIST_THRESHOLD {
+        return rsx! {
+            VirtualList { todos, on_todo_changed }
+        };
+    }
+
     rsx! {
         div { class: "space-y-4",
             for todo in todos {
@@ -251,32 +409,58 @@ fn TodoList(todos: Vec<TodoResponse>, on_todo_changed: EventHandler<()>) -> Elem
 #[component]
 fn TodoItem(todo: TodoResponse, on_changed: EventHandler<()>) -> Element {
     let mut editing = use_signal(|| false);
+    let todo_id = todo.id;
+    // Fetched only once opened, so a long list doesn't cost a request per todo
+    let mut related = use_signal(|| None::<AsyncState<Vec<RelatedTodoResponse>>>);
+    let related_view = match &*related.read() {
+        None => None,
+        Some(AsyncState::Loading) => Some("Loading related todos…".to_string()),
+        Some(AsyncState::Error(e)) => Some(format!("Could not load related todos: {e}")),
+        Some(AsyncState::Ready(todos)) => Some(format!("{} related", todos.len())),
+    };
+    let related_todos = related
+        .read()
+        .as_ref()
+        .and_then(AsyncState::value)
+        .cloned()
+        .unwrap_or_default();
 
     let priority_color = match todo.priority {
+        Priority::Critical => "border-l-purple-500 bg-purple-50",
+        Priority::VeryHigh => "border-l-orange-400 bg-orange-50",
         Priority::High => "border-l-red-400 bg-red-50",
         Priority::Medium => "border-l-yellow-400 bg-yellow-50",
         Priority::Low => "border-l-green-400 bg-green-50",
     };
 
     let priority_icon = match todo.priority {
+        Priority::Critical => "🚨",
+        Priority::VeryHigh => "🟠",
         Priority::High => "🔴",
         Priority::Medium => "🟡",
         Priority::Low => "🟢",
     };
 
     let priority_text = match todo.priority {
+        Priority::Critical => "Critical",
+        Priority::VeryHigh => "Very High",
         Priority::High => "High",
         Priority::Medium => "Medium",
         Priority::Low => "Low",
     };
 
     let priority_badge_color = match todo.priority {
+        Priority::Critical => "bg-purple-100 text-purple-700 border-purple-200",
+        Priority::VeryHigh => "bg-orange-100 text-orange-700 border-orange-200",
         Priority::High => "bg-red-100 text-red-700 border-red-200",
         Priority::Medium => "bg-yellow-100 text-yellow-700 border-yellow-200",
         Priority::Low => "bg-green-100 text-green-700 border-green-200",
     };
 
     let created_at_str = todo.created_at.format("%b %d, %Y at %H:%M").to_string();
+    let due_date_str = todo
+        .due_date
+        .map(|due| due.format("%b %d, %Y at %H:%M").to_string());
 
     if editing() {
         return rsx! {
@@ -292,7 +476,9 @@ fn TodoItem(todo: TodoResponse, on_changed: EventHandler<()>) -> Element {
     }
 
     rsx! {
-        div { class: "bg-white rounded-lg shadow-md border-l-4 {priority_color} p-6",
+        div {
+            id: "todo-{todo.id}",
+            class: "bg-white rounded-lg shadow-md border-l-4 {priority_color} p-6",
             div { class: "flex items-start gap-4",
                 // Checkbox
                 div { class: "pt-1",
@@ -304,14 +490,7 @@ fn TodoItem(todo: TodoResponse, on_changed: EventHandler<()>) -> Element {
                             let todo_id = todo.id;
                             let new_completed = !todo.completed;
                             spawn(async move {
-                                let req = UpdateTodoRequest {
-                                    title: None,
-                                    description: None,
-                                    due_date: None,
-                                    priority: None,
-                                    completed: Some(new_completed),
-                                };
-                                if update_todo(todo_id, req).await.is_ok() {
+                                if set_completed(todo_id, new_completed).await.is_ok() {
                                     on_changed.call(());
                                 }
                             });
@@ -322,6 +501,9 @@ fn TodoItem(todo: TodoResponse, on_changed: EventHandler<()>) -> Element {
                 // Content
                 div { class: "flex-1 min-w-0",
                     div { class: "flex items-center gap-3 mb-2 flex-wrap",
+                        if todo.pinned {
+                            span { class: "text-xl", title: "Pinned", "📌" }
+                        }
                         h3 {
                             class: if todo.completed { "text-2xl font-bold text-gray-400 line-through" } else { "text-2xl font-bold text-gray-900" },
                             "{todo.title}"
@@ -332,6 +514,16 @@ fn TodoItem(todo: TodoResponse, on_changed: EventHandler<()>) -> Element {
                         }
                     }
 
+                    if todo.progress > 0 && !todo.completed {
+                        div { class: "w-full h-1.5 bg-gray-200 rounded-full mb-3",
+                            div {
+                                class: "h-1.5 bg-blue-500 rounded-full",
+                                style: "width: {todo.progress}%;",
+                                title: "{todo.progress}% done"
+                            }
+                        }
+                    }
+
                     if let Some(desc) = &todo.description {
                         p {
                             class: if todo.completed { "text-gray-400 mb-3 line-through" } else { "text-gray-700 mb-3" },
@@ -339,19 +531,135 @@ fn TodoItem(todo: TodoResponse, on_changed: EventHandler<()>) -> Element {
                         }
                     }
 
+                    TagList { tags: todo.tags.clone() }
+
+                    // Only todos with tags can have related ones
+                    if !todo.tags.is_empty() {
+                        button {
+                            class: "inline-block text-sm text-blue-600 hover:underline mb-2",
+                            onclick: move |_| {
+                                if related.peek().is_some() {
+                                    related.set(None);
+                                    return;
+                                }
+                                related.set(Some(AsyncState::Loading));
+                                spawn(async move {
+                                    let fetched = match fetch_related_todos(todo_id).await {
+                                        Ok(todos) => AsyncState::Ready(todos),
+                                        Err(e) => AsyncState::Error(e),
+                                    };
+                                    related.set(Some(fetched));
+                                });
+                            },
+                            if related_view.is_some() { "Hide related" } else { "Show related" }
+                        }
+                    }
+
+                    if let Some(summary) = related_view {
+                        div { class: "text-sm text-gray-600 mb-2",
+                            p { "{summary}" }
+                            ul { class: "list-disc ml-5",
+                                for entry in related_todos {
+                                    li { key: "{entry.todo.id}",
+                                        "{entry.todo.title} ({entry.shared_tags} shared)"
+                                    }
+                                }
+                            }
+                        }
+                    }
+
                     div { class: "flex items-center text-sm text-gray-500",
                         span { class: "mr-1", "🕐" }
                         "{created_at_str}"
                     }
+
+                    if let Some(due) = due_date_str {
+                        div { class: "flex items-center text-sm text-gray-500 mt-1",
+                            span { class: "mr-1", "📅" }
+                            "Due {due}"
+                        }
+                    }
                 }
 
                 // Actions
                 div { class: "flex gap-2",
+                    select {
+                        class: "px-3 py-2 text-sm bg-gray-100 text-gray-700 rounded hover:bg-gray-200 cursor-pointer",
+                        value: "",
+                        onchange: move |e| {
+                            let Ok(hours) = e.value().parse::<i64>() else {
+                                return;
+                            };
+                            let todo_id = todo.id;
+                            // Pushed back from the current due date, or from now without one
+                            let req = DueDateRequest {
+                                due_date: Some(
+                                    todo.due_date.unwrap_or_else(chrono::Utc::now)
+                                        + chrono::Duration::hours(hours),
+                                ),
+                            };
+                            spawn(async move {
+                                if set_due_date(todo_id, req).await.is_ok() {
+                                    on_changed.call(());
+                                }
+                            });
+                        },
+                        option { value: "", disabled: true, "⏰ Snooze" }
+                        option { value: "1", "1 hour" }
+                        option { value: "8", "8 hours" }
+                        option { value: "24", "1 day" }
+                        option { value: "168", "1 week" }
+                    }
+                    button {
+                        class: "px-3 py-2 text-sm bg-gray-100 text-gray-700 rounded hover:bg-gray-200",
+                        onclick: move |_| {
+                            let todo_id = todo.id;
+                            let req = PinRequest {
+                                pinned: Some(!todo.pinned),
+                            };
+                            spawn(async move {
+                                if pin_todo(todo_id, req).await.is_ok() {
+                                    on_changed.call(());
+                                }
+                            });
+                        },
+                        if todo.pinned { "Unpin" } else { "Pin" }
+                    }
+                    if todo.started_at.is_some() {
+                        span { class: "px-3 py-2 text-sm bg-green-100 text-green-700 rounded",
+                            "In Progress"
+                        }
+                    } else {
+                        button {
+                            class: "px-3 py-2 text-sm bg-green-500 text-white rounded hover:bg-green-600",
+                            onclick: move |_| {
+                                let todo_id = todo.id;
+                                spawn(async move {
+                                    if start_todo(todo_id).await.is_ok() {
+                                        on_changed.call(());
+                                    }
+                                });
+                            },
+                            "▶ Start"
+                        }
+                    }
                     button {
                         class: "px-3 py-2 text-sm bg-blue-500 text-white rounded hover:bg-blue-600",
                         onclick: move |_| editing.set(true),
                         "Edit"
                     }
+                    button {
+                        class: "px-3 py-2 text-sm bg-gray-100 text-gray-700 rounded hover:bg-gray-200",
+                        onclick: move |_| {
+                            let todo_id = todo.id;
+                            spawn(async move {
+                                if duplicate_todo(todo_id).await.is_ok() {
+                                    on_changed.call(());
+                                }
+                            });
+                        },
+                        "Duplicate"
+                    }
                     button {
                         class: "px-3 py-2 text-sm bg-red-500 text-white rounded hover:bg-red-600",
                         onclick: move |_| {
@@ -379,6 +687,9 @@ fn EditTodoForm(
     let mut title = use_signal(|| todo.title.clone());
     let mut description = use_signal(|| todo.description.clone().unwrap_or_default());
     let mut priority = use_signal(|| todo.priority.clone());
+    let tags = use_signal(|| todo.tags.clone());
+    // The version being edited, so a save over someone else's change is refused
+    let opened_version = use_hook(|| todo.version);
     let mut submitting = use_signal(|| false);
 
     let on_submit = move |_| {
@@ -394,6 +705,7 @@ fn EditTodoForm(
             Some(Some(description()))
         };
         let new_priority = priority();
+        let new_tags = tags();
 
         submitting.set(true);
 
@@ -404,6 +716,11 @@ fn EditTodoForm(
                 due_date: None,
                 priority: Some(new_priority),
                 completed: None,
+                progress: None,
+                metadata: None,
+                estimate_hours: None,
+                tags: Some(new_tags),
+                expected_version: Some(opened_version),
             };
 
             match update_todo(todo_id, req).await {
@@ -451,11 +768,15 @@ fn EditTodoForm(
                             Priority::Low => "Low",
                             Priority::Medium => "Medium",
                             Priority::High => "High",
+                            Priority::VeryHigh => "VeryHigh",
+                            Priority::Critical => "Critical",
                         },
                         onchange: move |e| {
                             let p = match e.value().as_str() {
                                 "Low" => Priority::Low,
                                 "High" => Priority::High,
+                                "VeryHigh" => Priority::VeryHigh,
+                                "Critical" => Priority::Critical,
                                 _ => Priority::Medium,
                             };
                             priority.set(p);
@@ -463,9 +784,16 @@ fn EditTodoForm(
                         option { value: "Low", "🟢 Low" }
                         option { value: "Medium", "🟡 Medium" }
                         option { value: "High", "🔴 High" }
+                        option { value: "VeryHigh", "🟠 Very High" }
+                        option { value: "Critical", "🚨 Critical" }
                     }
                 }
 
+                div { class: "mb-5",
+                    label { class: "block text-sm font-semibold text-gray-700 mb-2", "Tags" }
+                    TagInput { tags }
+                }
+
                 div { class: "flex gap-3",
                     button {
                         r#type: "submit",
@@ -485,6 +813,129 @@ fn EditTodoForm(
     }
 }
 
+#[component]
+fn StatsPanel() -> Element {
+    let (open, set_open) = use_local_storage("stats_panel_open", false);
+    let mut data = use_signal(|| Vec::<(String, u64)>::new());
+    let mut remaining_hours = use_signal(|| Option::<f32>::None);
+
+    // Load this week's stats once on mount
+    use_effect(move || {
+        spawn(async move {
+            match fetch_stats().await {
+                Ok(stats) => remaining_hours.set(Some(stats.total_estimated_hours)),
+                Err(e) => error!(error = %e, "Failed to load stats"),
+            }
+
+            let today = chrono::Utc::now().date_naive();
+            let from = today - chrono::Duration::days(6);
+            let query = TimelineQuery {
+                granularity: Granularity::Day,
+                from: from.and_hms_opt(0, 0, 0).map(|at| at.and_utc()),
+                to: None,
+            };
+            match fetch_timeline(query).await {
+                Ok(buckets) => {
+                    // The API omits empty days, so fill in every day of the week
+                    let days = from.iter_days().take(7).map(|day| {
+                        let period = day.format("%Y-%m-%d").to_string();
+                        let created = buckets
+                            .iter()
+                            .find(|bucket| bucket.period == period)
+                            .map_or(0, |bucket| bucket.created);
+                        (day.format("%a").to_string(), created)
+                    });
+                    data.set(days.collect());
+                }
+                Err(e) => error!(error = %e, "Failed to load timeline stats"),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "bg-white rounded-2xl shadow-lg p-6 mb-8 border border-gray-100",
+            button {
+                class: "w-full flex items-center justify-between text-lg font-bold text-gray-800",
+                onclick: move |_| set_open(!open()),
+                span { "📊 Created this week" }
+                span { class: "text-gray-500", if open() { "▲" } else { "▼" } }
+            }
+            if let Some(hours) = remaining_hours().filter(|hours| *hours > 0.0) {
+                p { class: "text-sm text-gray-600 mt-2", "~{hours:.1} hours of work remaining" }
+            }
+            if open() {
+                StatsChart { data: data() }
+            }
+        }
+    }
+}
+
+#[component]
+fn StatsChart(data: Vec<(String, u64)>) -> Element {
+    const HEIGHT: f64 = 50.0;
+    let max = data
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let slot = 100.0 / data.len().max(1) as f64;
+
+    let bars: Vec<(String, u64, f64, f64, &str)> = data
+        .iter()
+        .enumerate()
+        .map(|(i, (label, count))| {
+            let ratio = *count as f64 / max;
+            let color = if ratio > 0.66 {
+                "#2563eb"
+            } else if ratio > 0.33 {
+                "#60a5fa"
+            } else {
+                "#bfdbfe"
+            };
+            (
+                label.clone(),
+                *count,
+                i as f64 * slot,
+                ratio * HEIGHT,
+                color,
+            )
+        })
+        .collect();
+    let bar_width = slot * 0.7;
+    let bar_offset = slot * 0.15;
+    let label_offset = slot / 2.0;
+
+    rsx! {
+        svg {
+            class: "w-full h-48 mt-4",
+            view_box: "0 0 100 60",
+            preserve_aspect_ratio: "none",
+            for (label, count, x, height, color) in bars {
+                g { key: "{label}",
+                    rect {
+                        x: "{x + bar_offset}",
+                        y: "{HEIGHT - height}",
+                        width: "{bar_width}",
+                        height: "{height}",
+                        rx: "1",
+                        fill: color,
+                        title { "{count}" }
+                    }
+                    text {
+                        x: "{x + label_offset}",
+                        y: "57",
+                        text_anchor: "middle",
+                        font_size: "4",
+                        fill: "#6b7280",
+                        "{label}"
+                    }
+                }
+            }
+        }
+    }
+}
+
 // API functions
 
 async fn fetch_todos() -> Result<Vec<TodoResponse>, String> {
@@ -543,6 +994,182 @@ async fn update_todo(id: uuid::Uuid, req: UpdateTodoRequest) -> Result<TodoRespo
     })
 }
 
+/// Marks a todo done or not done, leaving its other fields alone
+async fn set_completed(id: uuid::Uuid, completed: bool) -> Result<TodoResponse, String> {
+    let req = UpdateTodoRequest {
+        title: None,
+        description: None,
+        due_date: None,
+        priority: None,
+        completed: Some(completed),
+        progress: None,
+        metadata: None,
+        estimate_hours: None,
+        tags: None,
+        expected_version: None,
+    };
+    update_todo(id, req).await
+}
+
+async fn set_due_date(id: uuid::Uuid, req: DueDateRequest) -> Result<TodoResponse, String> {
+    let client = reqwest::Client::new();
+    info!(%id, "Rescheduling todo via API");
+    let response = client
+        .patch(&format!("{}/todos/{}/due-date", API_BASE, id))
+        .json(&req)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, %id, "Request to reschedule todo failed");
+            e.to_string()
+        })?;
+
+    response.json::<TodoResponse>().await.map_err(|e| {
+        error!(error = %e, %id, "Failed to deserialize rescheduled todo");
+        e.to_string()
+    })
+}
+
+async fn pin_todo(id: uuid::Uuid, req: PinRequest) -> Result<TodoResponse, String> {
+    let client = reqwest::Client::new();
+    info!(%id, "Pinning todo via API");
+    let response = client
+        .post(&format!("{}/todos/{}/pin", API_BASE, id))
+        .json(&req)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, %id, "Request to pin todo failed");
+            e.to_string()
+        })?;
+
+    response.json::<TodoResponse>().await.map_err(|e| {
+        error!(error = %e, %id, "Failed to deserialize pinned todo");
+        e.to_string()
+    })
+}
+
+async fn fetch_random_todo() -> Result<Option<TodoResponse>, String> {
+    let client = reqwest::Client::new();
+    info!("Fetching a random todo from API");
+    let response = client
+        .get(&format!("{}/todos/random", API_BASE))
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Request to fetch random todo failed");
+            e.to_string()
+        })?;
+
+    // 404 just means there's nothing left to do
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    response
+        .json::<TodoResponse>()
+        .await
+        .map(Some)
+        .map_err(|e| {
+            error!(error = %e, "Failed to deserialize random todo");
+            e.to_string()
+        })
+}
+
+async fn start_todo(id: uuid::Uuid) -> Result<TodoResponse, String> {
+    let client = reqwest::Client::new();
+    info!(%id, "Starting todo via API");
+    let response = client
+        .post(&format!("{}/todos/{}/start", API_BASE, id))
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, %id, "Request to start todo failed");
+            e.to_string()
+        })?;
+
+    response.json::<TodoResponse>().await.map_err(|e| {
+        error!(error = %e, %id, "Failed to deserialize started todo");
+        e.to_string()
+    })
+}
+
+async fn duplicate_todo(id: uuid::Uuid) -> Result<TodoResponse, String> {
+    let client = reqwest::Client::new();
+    info!(%id, "Duplicating todo via API");
+    let response = client
+        .post(&format!("{}/todos/{}/duplicate", API_BASE, id))
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, %id, "Request to duplicate todo failed");
+            e.to_string()
+        })?;
+
+    response.json::<TodoResponse>().await.map_err(|e| {
+        error!(error = %e, %id, "Failed to deserialize duplicated todo");
+        e.to_string()
+    })
+}
+
+async fn fetch_related_todos(id: uuid::Uuid) -> Result<Vec<RelatedTodoResponse>, String> {
+    let client = reqwest::Client::new();
+    info!(%id, "Fetching related todos from API");
+    let response = client
+        .get(&format!("{}/todos/{}/related", API_BASE, id))
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, %id, "Request to fetch related todos failed");
+            e.to_string()
+        })?;
+
+    response
+        .json::<Vec<RelatedTodoResponse>>()
+        .await
+        .map_err(|e| {
+            error!(error = %e, %id, "Failed to deserialize related todos");
+            e.to_string()
+        })
+}
+
+async fn fetch_stats() -> Result<StatsResponse, String> {
+    let client = reqwest::Client::new();
+    info!("Fetching stats from API");
+    let response = client
+        .get(&format!("{}/todos/stats", API_BASE))
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Request to fetch stats failed");
+            e.to_string()
+        })?;
+
+    response.json::<StatsResponse>().await.map_err(|e| {
+        error!(error = %e, "Failed to deserialize stats");
+        e.to_string()
+    })
+}
+
+async fn fetch_timeline(query: TimelineQuery) -> Result<Vec<TimelineBucket>, String> {
+    let client = reqwest::Client::new();
+    info!("Fetching timeline stats from API");
+    let response = client
+        .get(&format!("{}/todos/stats/timeline", API_BASE))
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Request to fetch timeline stats failed");
+            e.to_string()
+        })?;
+
+    response.json::<Vec<TimelineBucket>>().await.map_err(|e| {
+        error!(error = %e, "Failed to deserialize timeline stats");
+        e.to_string()
+    })
+}
+
 async fn delete_todo(id: uuid::Uuid) -> Result<(), String> {
     let client = reqwest::Client::new();
     info!(%id, "Deleting todo via API");
@@ -557,3 +1184,61 @@ async fn delete_todo(id: uuid::Uuid) -> Result<(), String> {
 
     Ok(())
 }
+
+async fn search_todos(query: &str) -> Result<Vec<TodoResponse>, String> {
+    let client = reqwest::Client::new();
+    info!(query, "Searching todos via API");
+    let response = client
+        .get(&format!("{}/todos/search", API_BASE))
+        .query(&SearchQuery {
+            q: query.to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Request to search todos failed");
+            e.to_string()
+        })?;
+
+    response.json::<Vec<TodoResponse>>().await.map_err(|e| {
+        error!(error = %e, "Failed to deserialize search results");
+        e.to_string()
+    })
+}
+
+/// Deletes every completed todo and returns how many were removed
+async fn delete_completed_todos() -> Result<u64, String> {
+    let client = reqwest::Client::new();
+    info!("Deleting completed todos via API");
+    let response = client
+        .delete(&format!("{}/todos/completed", API_BASE))
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Request to delete completed todos failed");
+            e.to_string()
+        })?;
+
+    response
+        .json::<DeleteCompletedResponse>()
+        .await
+        .map(|response| response.deleted)
+        .map_err(|e| {
+            error!(error = %e, "Failed to deserialize deleted count");
+            e.to_string()
+        })
+}
+
+/// Scrolls a todo into view and briefly outlines it
+fn highlight_todo(id: uuid::Uuid) {
+    document::eval(&format!(
+        r#"
+        const el = document.getElementById("todo-{id}");
+        if (el) {{
+            el.scrollIntoView({{ behavior: "smooth", block: "center" }});
+            el.style.outline = "4px solid #60a5fa";
+            setTimeout(() => {{ el.style.outline = ""; }}, 2000);
+        }}
+        "#
+    ));
+}
@@ -0,0 +1,71 @@
+//! Editing a todo's tags as removable chips.
+
+use dioxus::prelude::*;
+
+/// Shows `tags` as chips with a remove button each, plus an input that adds
+/// its text as a tag on Enter. Blank and repeated tags are ignored.
+#[component]
+pub fn TagInput(mut tags: Signal<Vec<String>>) -> Element {
+    let mut draft = use_signal(String::new);
+
+    let mut add_draft = move || {
+        let tag = draft().trim().to_string();
+        if !tag.is_empty() && !tags.read().contains(&tag) {
+            tags.write().push(tag);
+        }
+        draft.set(String::new());
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-2 w-full px-4 py-2 border-2 border-gray-200 rounded-xl focus-within:border-blue-500",
+            for (index, tag) in tags().into_iter().enumerate() {
+                span {
+                    key: "{tag}",
+                    class: "flex items-center gap-1 px-3 py-1 text-sm font-semibold rounded-full bg-blue-100 text-blue-700",
+                    "#{tag}"
+                    button {
+                        r#type: "button",
+                        class: "text-blue-400 hover:text-blue-700",
+                        title: "Remove tag",
+                        onclick: move |_| {
+                            tags.write().remove(index);
+                        },
+                        "×"
+                    }
+                }
+            }
+            input {
+                r#type: "text",
+                class: "flex-1 min-w-24 py-1 focus:outline-none",
+                value: "{draft}",
+                oninput: move |e| draft.set(e.value()),
+                // Enter adds the tag instead of submitting the surrounding form
+                onkeydown: move |e| {
+                    if e.key() == Key::Enter {
+                        e.prevent_default();
+                        add_draft();
+                    }
+                },
+                placeholder: "Add a tag and press Enter"
+            }
+        }
+    }
+}
+
+/// Small read-only chips for a todo's tags
+#[component]
+pub fn TagList(tags: Vec<String>) -> Element {
+    rsx! {
+        if !tags.is_empty() {
+            div { class: "flex flex-wrap gap-2 mb-3",
+                for tag in tags {
+                    span {
+                        key: "{tag}",
+                        class: "px-2 py-0.5 text-xs font-semibold rounded-full bg-blue-100 text-blue-700",
+                        "#{tag}"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Windowed rendering for long todo lists.
+
+use std::ops::Range;
+
+use dioxus::prelude::*;
+use todoapp_transfer::TodoResponse;
+
+use crate::{set_completed, TodoItem};
+
+/// Height of the scrolling container, in pixels
+const VIEWPORT_HEIGHT: f64 = 640.0;
+/// Height of each row, in pixels
+const ITEM_HEIGHT: f64 = 56.0;
+/// Rows rendered above and below the viewport so fast scrolling doesn't flash
+const BUFFER: usize = 3;
+
+/// Renders only the todos that are scrolled into view, plus a small buffer.
+///
+/// Positions are computed without measuring the DOM, so each todo is shown as
+/// a single-line [`TodoRow`] of fixed height. Clicking a row opens the full
+/// [`TodoItem`] above the list, where it can be as tall as it needs.
+#[component]
+pub fn VirtualList(todos: Vec<TodoResponse>, on_todo_changed: EventHandler<()>) -> Element {
+    let mut scroll_top = use_signal(|| 0.0);
+    let mut selected = use_signal(|| None::<uuid::Uuid>);
+
+    let selected_todo = selected().and_then(|id| todos.iter().find(|todo| todo.id == id).cloned());
+    let total_height = todos.len() as f64 * ITEM_HEIGHT;
+    let rows: Vec<(usize, f64, TodoResponse)> = visible_range(scroll_top(), todos.len())
+        .map(|index| (index, index as f64 * ITEM_HEIGHT, todos[index].clone()))
+        .collect();
+
+    rsx! {
+        if let Some(todo) = selected_todo {
+            div { class: "mb-4",
+                TodoItem {
+                    todo,
+                    on_changed: move |_| on_todo_changed.call(())
+                }
+            }
+        }
+        div {
+            class: "overflow-y-auto",
+            style: "height: {VIEWPORT_HEIGHT}px;",
+            onscroll: move |e| scroll_top.set(e.data().scroll_top()),
+            div {
+                class: "relative",
+                style: "height: {total_height}px;",
+                for (index, top, todo) in rows {
+                    div {
+                        key: "{todo.id}",
+                        "data-index": "{index}",
+                        class: "absolute inset-x-0",
+                        style: "top: {top}px; height: {ITEM_HEIGHT}px;",
+                        TodoRow {
+                            selected: selected() == Some(todo.id),
+                            todo,
+                            on_select: move |id| {
+                                if selected() == Some(id) {
+                                    selected.set(None);
+                                } else {
+                                    selected.set(Some(id));
+                                }
+                            },
+                            on_changed: move |_| on_todo_changed.call(())
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One line of a [`VirtualList`]: completion, title and due date
+#[component]
+fn TodoRow(
+    todo: TodoResponse,
+    selected: bool,
+    on_select: EventHandler<uuid::Uuid>,
+    on_changed: EventHandler<()>,
+) -> Element {
+    let todo_id = todo.id;
+    let due_date_str = todo.due_date.map(|due| due.format("%b %d").to_string());
+
+    rsx! {
+        div {
+            class: if selected { "flex items-center gap-3 h-full px-4 border-b border-gray-200 bg-blue-50" } else { "flex items-center gap-3 h-full px-4 border-b border-gray-200 bg-white" },
+            input {
+                r#type: "checkbox",
+                class: "w-5 h-5 cursor-pointer",
+                checked: todo.completed,
+                onchange: move |_| {
+                    let new_completed = !todo.completed;
+                    spawn(async move {
+                        if set_completed(todo_id, new_completed).await.is_ok() {
+                            on_changed.call(());
+                        }
+                    });
+                }
+            }
+            if todo.pinned {
+                span { title: "Pinned", "📌" }
+            }
+            button {
+                class: if todo.completed { "flex-1 min-w-0 truncate text-left text-gray-400 line-through" } else { "flex-1 min-w-0 truncate text-left text-gray-900" },
+                onclick: move |_| on_select.call(todo_id),
+                "{todo.title}"
+            }
+            if let Some(due) = due_date_str {
+                span { class: "text-sm text-gray-500 whitespace-nowrap", "📅 {due}" }
+            }
+        }
+    }
+}
+
+/// Indices of the rows intersecting the viewport, widened by `BUFFER`
+fn visible_range(scroll_top: f64, len: usize) -> Range<usize> {
+    let first = (scroll_top.max(0.0) / ITEM_HEIGHT).floor() as usize;
+    let visible = (VIEWPORT_HEIGHT / ITEM_HEIGHT).ceil() as usize;
+    let end = (first + visible + BUFFER).min(len);
+    let start = first.saturating_sub(BUFFER).min(end);
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use todoapp_transfer::{FieldTimestamps, Priority};
+
+    fn todo(n: usize) -> TodoResponse {
+        let now = Utc::now();
+        TodoResponse {
+            id: uuid::Uuid::from_u128(n as u128),
+            title: format!("Todo {n}"),
+            description: None,
+            due_date: None,
+            priority: Priority::Medium,
+            completed: false,
+            completed_at: None,
+            progress: 0,
+            pinned: false,
+            estimate_hours: None,
+            started_at: None,
+            created_at: now,
+            updated_at: now,
+            version: 0,
+            field_updated_at: FieldTimestamps {
+                title: now,
+                description: now,
+                due_date: now,
+                priority: now,
+                completed: now,
+                progress: now,
+                pinned: now,
+                estimate_hours: now,
+                metadata: now,
+                tags: now,
+            },
+            tags: Vec::new(),
+            metadata: serde_json::Value::Null,
+            is_overdue: false,
+        }
+    }
+
+    #[test]
+    fn test_renders_a_window_of_a_long_list() {
+        let len = 500;
+
+        assert!(visible_range(0.0, len).len() < 30);
+        assert_eq!(visible_range(0.0, len).start, 0);
+
+        let middle = visible_range(250.0 * ITEM_HEIGHT, len);
+        assert!(middle.len() < 30);
+        assert!(middle.contains(&250));
+
+        let bottom = visible_range(len as f64 * ITEM_HEIGHT, len);
+        assert!(bottom.len() < 30);
+        assert_eq!(bottom.end, len);
+    }
+
+    #[test]
+    fn test_renders_few_rows_into_the_dom() {
+        fn app() -> Element {
+            rsx! {
+                VirtualList {
+                    todos: (0..500).map(todo).collect::<Vec<_>>(),
+                    on_todo_changed: |_| {}
+                }
+            }
+        }
+
+        let mut dom = VirtualDom::new(app);
+        dom.rebuild_in_place();
+        let html = dioxus_ssr::render(&dom);
+
+        let rows = html.matches("data-index=").count();
+        assert!(rows > 0);
+        assert!(rows < 30, "rendered {rows} rows for 500 todos");
+        assert!(html.contains("Todo 0"));
+        assert!(!html.contains("Todo 499"));
+    }
+}
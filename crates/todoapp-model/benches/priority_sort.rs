@@ -0,0 +1,38 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use todoapp_model::{SledBenchHarness, Todo};
+
+const RECORDS: usize = 10_000;
+
+fn priority_sort(c: &mut Criterion) {
+    let harness = SledBenchHarness::with_n_todos(RECORDS);
+    let db = harness.db();
+
+    let mut group = c.benchmark_group("priority_desc_10k");
+    group.bench_function("load_then_sort", |b| {
+        b.iter(|| {
+            let mut todos = db.get_all().unwrap();
+            todos.sort_by_key(|todo| std::cmp::Reverse(todo.priority.clone()));
+            black_box(todos)
+        })
+    });
+    group.bench_function("priority_index", |b| {
+        b.iter(|| {
+            let todos: Vec<Todo> = db
+                .iter_by_priority_descending()
+                .collect::<anyhow::Result<_>>()
+                .unwrap();
+            black_box(todos)
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("lookup_10k");
+    group.bench_function("get_random", |b| {
+        b.iter(|| black_box(db.get(&harness.random_existing_id()).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, priority_sort);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+//! Async wrappers around the blocking [`TodoDb`] CRUD methods.
+//!
+//! sled blocks the calling thread on I/O, so each call runs on tokio's
+//! blocking pool instead of stalling the async executor.
+
+use crate::{Todo, TodoDb};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use uuid::Uuid;
+
+impl TodoDb {
+    pub async fn insert_async(&self, todo: Todo) -> Result<()> {
+        self.run_blocking(move |db| db.insert(&todo)).await
+    }
+
+    pub async fn get_async(&self, id: Uuid) -> Result<Option<Todo>> {
+        self.run_blocking(move |db| db.get(&id)).await
+    }
+
+    pub async fn exists_async(&self, id: Uuid) -> Result<bool> {
+        self.run_blocking(move |db| db.exists(&id)).await
+    }
+
+    pub async fn get_all_async(&self) -> Result<Vec<Todo>> {
+        self.run_blocking(|db| db.get_all()).await
+    }
+
+    pub async fn update_async(&self, todo: Todo) -> Result<Todo> {
+        self.run_blocking(move |db| db.update(&todo)).await
+    }
+
+    pub async fn update_if_version_async(&self, todo: Todo, expected: u64) -> Result<Todo> {
+        self.run_blocking(move |db| db.update_if_version(&todo, expected))
+            .await
+    }
+
+    pub async fn update_metadata_async(
+        &self,
+        id: Uuid,
+        patch: Value,
+        max_bytes: usize,
+    ) -> Result<Option<Value>> {
+        self.run_blocking(move |db| db.update_metadata(&id, patch, max_bytes))
+            .await
+    }
+
+    pub async fn delete_async(&self, id: Uuid) -> Result<bool> {
+        self.run_blocking(move |db| db.delete(&id)).await
+    }
+
+    async fn run_blocking<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(TodoDb) -> Result<T> + Send + 'static,
+    {
+        let db = self.clone();
+        tokio::task::spawn_blocking(move || f(db))
+            .await
+            .context("Database task failed")?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Priority, Todo, TodoDb};
+
+    #[tokio::test]
+    async fn test_async_crud_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut todo = Todo::new("Async".to_string(), None, None, Priority::Low);
+        db.insert_async(todo.clone()).await.unwrap();
+        assert_eq!(db.get_all_async().await.unwrap().len(), 1);
+
+        todo.mark_completed();
+        db.update_async(todo.clone()).await.unwrap();
+        assert!(db.get_async(todo.id).await.unwrap().unwrap().completed);
+
+        assert!(db.delete_async(todo.id).await.unwrap());
+        assert!(db.get_async(todo.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,48 @@
+//! Shared setup for benchmarks, enabled by the `bench` feature.
+
+use crate::{Priority, Todo, TodoDb};
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+
+/// A temporary database pre-filled with predictable todos
+pub struct SledBenchHarness {
+    db: TodoDb,
+    ids: Vec<Uuid>,
+}
+
+impl SledBenchHarness {
+    /// Inserts `n` todos with rotating priorities into a fresh temporary
+    /// database that's deleted when the harness is dropped
+    pub fn with_n_todos(n: usize) -> Self {
+        let path = std::env::temp_dir().join(format!("todoapp-bench-{}", Uuid::new_v4()));
+        let db = TodoDb::builder(path)
+            .temporary(true)
+            .open()
+            .expect("Failed to open benchmark database");
+        let ids = (0..n)
+            .map(|i| {
+                let priority = match i % 3 {
+                    0 => Priority::Low,
+                    1 => Priority::Medium,
+                    _ => Priority::High,
+                };
+                let todo = Todo::new(format!("Todo {}", i), None, None, priority);
+                db.insert(&todo).expect("Failed to insert benchmark todo");
+                todo.id
+            })
+            .collect();
+        Self { db, ids }
+    }
+
+    pub fn db(&self) -> &TodoDb {
+        &self.db
+    }
+
+    /// Picks the id of one of the inserted todos
+    pub fn random_existing_id(&self) -> Uuid {
+        *self
+            .ids
+            .choose(&mut rand::thread_rng())
+            .expect("harness has no todos")
+    }
+}
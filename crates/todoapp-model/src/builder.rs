@@ -0,0 +1,119 @@
+//! Named-field construction of new todos.
+
+use crate::{Priority, Todo};
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+/// Why a [`TodoBuilder`] could not build a todo
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuilderError {
+    MissingTitle,
+    /// The title is empty or only whitespace
+    EmptyTitle,
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::MissingTitle => write!(f, "a todo needs a title"),
+            BuilderError::EmptyTitle => write!(f, "a todo title must not be empty"),
+        }
+    }
+}
+
+impl std::error::Error for BuilderError {}
+
+/// Builds a [`Todo`] without [`Todo::new`]'s positional options. Only the
+/// title is required; the priority defaults to `Medium`.
+#[derive(Debug, Clone, Default)]
+pub struct TodoBuilder {
+    title: Option<String>,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Option<Priority>,
+}
+
+impl TodoBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: String) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    pub fn description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn due_date(mut self, due_date: DateTime<Utc>) -> Self {
+        self.due_date = Some(due_date);
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn build(self) -> Result<Todo, BuilderError> {
+        let title = self.title.ok_or(BuilderError::MissingTitle)?;
+        if title.trim().is_empty() {
+            return Err(BuilderError::EmptyTitle);
+        }
+        Ok(Todo::new(
+            title,
+            self.description,
+            self.due_date,
+            self.priority.unwrap_or(Priority::Medium),
+        ))
+    }
+}
+
+impl Todo {
+    pub fn builder() -> TodoBuilder {
+        TodoBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_sets_fields() {
+        let due = Utc::now();
+        let todo = Todo::builder()
+            .title("Water plants".to_string())
+            .description("Balcony too".to_string())
+            .due_date(due)
+            .priority(Priority::High)
+            .build()
+            .unwrap();
+        assert_eq!(todo.title, "Water plants");
+        assert_eq!(todo.description.as_deref(), Some("Balcony too"));
+        assert_eq!(todo.due_date, Some(due));
+        assert_eq!(todo.priority, Priority::High);
+
+        let todo = Todo::builder()
+            .title("Defaults".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(todo.description, None);
+        assert_eq!(todo.priority, Priority::Medium);
+    }
+
+    #[test]
+    fn test_builder_requires_title() {
+        assert_eq!(
+            TodoBuilder::new().build().unwrap_err(),
+            BuilderError::MissingTitle
+        );
+        assert_eq!(
+            Todo::builder().title("  ".to_string()).build().unwrap_err(),
+            BuilderError::EmptyTitle
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Conversions between model types and the data transfer objects of
+//! `todoapp-transfer`.
+
+use crate::{FieldTimestamps, Priority, Todo, TodoDb, clean_tags};
+use anyhow::{Context, Result};
+use std::io::Write;
+use todoapp_transfer::{
+    CreateTodoRequest, FieldTimestamps as TransferFieldTimestamps, Priority as TransferPriority,
+    TodoResponse,
+};
+
+impl From<TransferPriority> for Priority {
+    fn from(priority: TransferPriority) -> Self {
+        match priority {
+            TransferPriority::Low => Priority::Low,
+            TransferPriority::Medium => Priority::Medium,
+            TransferPriority::High => Priority::High,
+            TransferPriority::VeryHigh => Priority::VeryHigh,
+            TransferPriority::Critical => Priority::Critical,
+        }
+    }
+}
+
+impl From<Priority> for TransferPriority {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Low => TransferPriority::Low,
+            Priority::Medium => TransferPriority::Medium,
+            Priority::High => TransferPriority::High,
+            Priority::VeryHigh => TransferPriority::VeryHigh,
+            Priority::Critical => TransferPriority::Critical,
+        }
+    }
+}
+
+/// A new todo with a fresh id. A `created_at` in the request backdates it,
+/// as for imports; the request is not validated here.
+impl From<CreateTodoRequest> for Todo {
+    fn from(req: CreateTodoRequest) -> Self {
+        let mut todo = match req.created_at {
+            Some(created_at) => Todo::new_with_timestamps(
+                req.title,
+                req.description,
+                req.due_date,
+                req.priority.into(),
+                created_at,
+                created_at,
+            ),
+            None => Todo::new(
+                req.title,
+                req.description,
+                req.due_date,
+                req.priority.into(),
+            ),
+        };
+        if let Some(metadata) = req.metadata {
+            todo.metadata = metadata;
+        }
+        todo.estimate_hours = req.estimate_hours;
+        if let Some(tags) = req.tags {
+            todo.tags = clean_tags(tags);
+        }
+        todo
+    }
+}
+
+/// The response for `todo`, with `is_overdue` computed as of now
+impl From<Todo> for TodoResponse {
+    fn from(todo: Todo) -> Self {
+        let is_overdue = todo.is_overdue();
+        TodoResponse {
+            id: todo.id,
+            title: todo.title,
+            description: todo.description,
+            due_date: todo.due_date,
+            priority: todo.priority.into(),
+            completed: todo.completed,
+            completed_at: todo.completed_at,
+            progress: todo.progress,
+            pinned: todo.pinned,
+            estimate_hours: todo.estimate_hours,
+            started_at: todo.started_at,
+            created_at: todo.created_at,
+            updated_at: todo.updated_at,
+            version: todo.version,
+            field_updated_at: todo.field_updated_at.into(),
+            tags: todo.tags,
+            metadata: todo.metadata,
+            is_overdue,
+        }
+    }
+}
+
+impl From<FieldTimestamps> for TransferFieldTimestamps {
+    fn from(timestamps: FieldTimestamps) -> Self {
+        TransferFieldTimestamps {
+            title: timestamps.title,
+            description: timestamps.description,
+            due_date: timestamps.due_date,
+            priority: timestamps.priority,
+            completed: timestamps.completed,
+            progress: timestamps.progress,
+            pinned: timestamps.pinned,
+            estimate_hours: timestamps.estimate_hours,
+            metadata: timestamps.metadata,
+            tags: timestamps.tags,
+        }
+    }
+}
+
+impl TodoDb {
+    /// Streams every todo into `writer` as a JSON array of [`TodoResponse`]s,
+    /// oldest first, without holding them all in memory. The items can be
+    /// imported again as `CreateTodoRequest`s. Returns the number of todos
+    /// written.
+    pub fn export_to_json_writer<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        writer.write_all(b"[").context("Failed to write export")?;
+        let mut count = 0;
+        for todo in self.iter_all() {
+            if count > 0 {
+                writer.write_all(b",").context("Failed to write export")?;
+            }
+            serde_json::to_writer(&mut *writer, &TodoResponse::from(todo?))
+                .context("Failed to write todo")?;
+            count += 1;
+        }
+        writer.write_all(b"]").context("Failed to write export")?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::empty_metadata;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_priority_roundtrip() {
+        for priority in Priority::ALL {
+            let transfer = TransferPriority::from(priority.clone());
+            assert_eq!(Priority::from(transfer), priority);
+        }
+    }
+
+    #[test]
+    fn test_todo_from_create_request() {
+        let due = Utc.with_ymd_and_hms(2025, 6, 1, 9, 0, 0).unwrap();
+        let todo = Todo::from(CreateTodoRequest {
+            title: "Renew passport".to_string(),
+            description: Some("Bring photos".to_string()),
+            due_date: Some(due),
+            priority: TransferPriority::High,
+            metadata: Some(serde_json::json!({ "office": "city hall" })),
+            estimate_hours: Some(1.5),
+            tags: Some(vec![
+                " errand ".to_string(),
+                String::new(),
+                "errand".to_string(),
+            ]),
+            created_at: None,
+        });
+        assert_eq!(todo.title, "Renew passport");
+        assert_eq!(todo.description.as_deref(), Some("Bring photos"));
+        assert_eq!(todo.due_date, Some(due));
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.metadata["office"], "city hall");
+        assert_eq!(todo.estimate_hours, Some(1.5));
+        assert_eq!(todo.tags, vec!["errand"]);
+        assert!(!todo.completed);
+        assert_eq!(todo.created_at, todo.updated_at);
+
+        let todo: Todo = CreateTodoRequest {
+            title: "Bare".to_string(),
+            description: None,
+            due_date: None,
+            priority: TransferPriority::Low,
+            metadata: None,
+            estimate_hours: None,
+            tags: None,
+            created_at: None,
+        }
+        .into();
+        assert_eq!(todo.description, None);
+        assert_eq!(todo.due_date, None);
+        assert_eq!(todo.estimate_hours, None);
+        assert!(todo.tags.is_empty());
+        assert_eq!(todo.metadata, empty_metadata());
+    }
+
+    #[test]
+    fn test_todo_response_from_todo() {
+        let mut todo = Todo::new(
+            "Late".to_string(),
+            None,
+            Some(Utc::now() - chrono::Duration::days(1)),
+            Priority::Medium,
+        );
+        todo.set_pinned(true);
+        let response = TodoResponse::from(todo.clone());
+        assert_eq!(response.id, todo.id);
+        assert_eq!(response.title, "Late");
+        assert_eq!(response.priority, TransferPriority::Medium);
+        assert!(response.pinned);
+        assert_eq!(
+            response.field_updated_at.pinned,
+            todo.field_updated_at.pinned
+        );
+        assert!(response.is_overdue);
+    }
+
+    #[test]
+    fn test_json_export_imports_again() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut empty = Vec::new();
+        assert_eq!(db.export_to_json_writer(&mut empty).unwrap(), 0);
+        assert_eq!(empty, b"[]");
+
+        let mut first = Todo::new("First".to_string(), None, None, Priority::Low);
+        first.metadata = serde_json::json!({ "source": "mail" });
+        first.set_tags(vec!["home".to_string()]);
+        db.insert(&first).unwrap();
+        db.insert(&Todo::new("Second".to_string(), None, None, Priority::High))
+            .unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(db.export_to_json_writer(&mut out).unwrap(), 2);
+        let exported: Vec<CreateTodoRequest> = serde_json::from_slice(&out).unwrap();
+        let imported: Vec<Todo> = exported.into_iter().map(Todo::from).collect();
+        let titles: Vec<&str> = imported.iter().map(|todo| todo.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Second"]);
+        assert_eq!(imported[0].metadata, first.metadata);
+        assert_eq!(imported[0].tags, first.tags);
+        assert_eq!(imported[0].created_at, first.created_at);
+        assert_eq!(imported[1].priority, Priority::High);
+    }
+}
@@ -1,18 +1,205 @@
-use crate::Todo;
-use anyhow::{Context, Result};
-use sled::Db;
+use crate::{
+    Granularity, NotificationPreference, SortBy, SortDirection, Summary, TimelineBucket, Todo,
+    TodoQuery,
+};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use serde::ser::{Error as _, SerializeStruct, Serializer};
+use serde_json::Value;
+use sled::transaction::{ConflictableTransactionError, TransactionError, Transactional};
+use sled::{Batch, Db, Tree};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::Write;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::watch;
 use uuid::Uuid;
 
+const PRIORITY_INDEX_TREE: &str = "priority_index";
+
+/// Written and removed again by [`TodoDb::ping`], in a tree of its own so
+/// readers of the todos never see it
+
+/// One tree of a snapshot: sled's collection type, name and key-value pairs
+type SnapshotCollection = (Vec<u8>, Vec<u8>, Vec<Vec<Vec<u8>>>);
+const NOTIFICATION_PREFS_TREE: &str = "notification_prefs";
+
+/// Callback invoked with the affected todo after a successful write
+pub type Hook = Box<dyn Fn(&Todo) + Send + Sync>;
+
+/// Handle to the todo store. Clones are cheap and share the same database.
+#[derive(Clone)]
 pub struct TodoDb {
     db: Db,
+    path: Arc<Path>,
+    /// Secondary index keyed by `priority rank | created_at | id`
+    priority_index: Tree,
+    /// Reminder preferences keyed by user
+    notification_prefs: Tree,
+    on_insert: Arc<Vec<Hook>>,
+    on_update: Arc<Vec<Hook>>,
+    on_delete: Arc<Vec<Hook>>,
+    /// Bumped after every write, see [`TodoDb::watch_all`]
+    changes: Arc<watch::Sender<u64>>,
+}
+
+/// Outcome of [`TodoDb::reindex`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReindexReport {
+    pub records_processed: u64,
+    /// Names of the index trees that were rebuilt
+    pub indexes_rebuilt: Vec<String>,
+}
+
+/// A conditional update found the todo at another version than expected,
+/// see [`TodoDb::update_if_version`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionConflict {
+    pub expected: u64,
+    pub current: u64,
+}
+
+impl fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "todo is at version {}, not {}",
+            self.current, self.expected
+        )
+    }
+}
+
+impl std::error::Error for VersionConflict {}
+
+/// Metadata whose serialized size is over the caller's limit, see
+/// [`TodoDb::update_metadata`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataTooLarge {
+    pub size: usize,
+    pub limit: usize,
+}
+
+impl MetadataTooLarge {
+    /// Fails when `metadata` serializes to more than `limit` bytes
+    pub fn check(metadata: &Value, limit: usize) -> Result<(), Self> {
+        let size = metadata.to_string().len();
+        if size > limit {
+            return Err(Self { size, limit });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MetadataTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Metadata is {} bytes, the limit is {} bytes",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for MetadataTooLarge {}
+
+/// Configures and opens a [`TodoDb`]
+pub struct TodoDbBuilder {
+    path: PathBuf,
+    cache_capacity_bytes: Option<u64>,
+    temporary: bool,
+    on_insert: Vec<Hook>,
+    on_update: Vec<Hook>,
+    on_delete: Vec<Hook>,
+}
+
+impl TodoDbBuilder {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            cache_capacity_bytes: None,
+            temporary: false,
+            on_insert: Vec::new(),
+            on_update: Vec::new(),
+            on_delete: Vec::new(),
+        }
+    }
+
+    /// Overrides sled's page cache size; sled's default is used otherwise
+    pub fn cache_capacity_bytes(mut self, bytes: u64) -> Self {
+        self.cache_capacity_bytes = Some(bytes);
+        self
+    }
+
+    /// Deletes the database files once the last handle is dropped
+    pub fn temporary(mut self, temporary: bool) -> Self {
+        self.temporary = temporary;
+        self
+    }
+
+    pub fn on_insert(mut self, hook: impl Fn(&Todo) + Send + Sync + 'static) -> Self {
+        self.on_insert.push(Box::new(hook));
+        self
+    }
+
+    pub fn on_update(mut self, hook: impl Fn(&Todo) + Send + Sync + 'static) -> Self {
+        self.on_update.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a hook that receives the todo as it was before deletion
+    pub fn on_delete(mut self, hook: impl Fn(&Todo) + Send + Sync + 'static) -> Self {
+        self.on_delete.push(Box::new(hook));
+        self
+    }
+
+    pub fn open(self) -> Result<TodoDb> {
+        let mut config = sled::Config::new()
+            .path(&self.path)
+            .temporary(self.temporary);
+        if let Some(bytes) = self.cache_capacity_bytes {
+            config = config.cache_capacity(bytes);
+        }
+        let db = config.open().context("Failed to open sled database")?;
+        crate::migration::upgrade(&db)?;
+        let priority_index = db
+            .open_tree(PRIORITY_INDEX_TREE)
+            .context("Failed to open priority index")?;
+        let notification_prefs = db
+            .open_tree(NOTIFICATION_PREFS_TREE)
+            .context("Failed to open notification preferences")?;
+        let todo_db = TodoDb {
+            db,
+            path: Arc::from(self.path),
+            priority_index,
+            notification_prefs,
+            on_insert: Arc::new(self.on_insert),
+            on_update: Arc::new(self.on_update),
+            on_delete: Arc::new(self.on_delete),
+            changes: Arc::new(watch::channel(0).0),
+        };
+        // Databases created before the index existed need it built once
+        if todo_db.priority_index.len() != todo_db.db.len() {
+            todo_db.rebuild_priority_index()?;
+        }
+        Ok(todo_db)
+    }
 }
 
 impl TodoDb {
-    pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self> {
-        let db = sled::open(path).context("Failed to open sled database")?;
-        Ok(Self { db })
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        TodoDbBuilder::new(path).open()
+    }
+
+    pub fn builder(path: impl AsRef<Path>) -> TodoDbBuilder {
+        TodoDbBuilder::new(path)
     }
 
+    #[must_use = "check whether the database operation succeeded"]
     pub fn insert(&self, todo: &Todo) -> Result<()> {
         let key = todo.id.as_bytes();
         let config = bincode::config::standard();
@@ -21,10 +208,22 @@ impl TodoDb {
         self.db
             .insert(key, value)
             .context("Failed to insert todo")?;
+        self.priority_index
+            .insert(priority_index_key(todo), INDEX_VALUE)
+            .context("Failed to update priority index")?;
         self.db.flush().context("Failed to flush database")?;
+        self.notify_change();
+        run_hooks(&self.on_insert, todo);
         Ok(())
     }
 
+    /// Whether a todo with `id` is stored, without decoding it
+    pub fn exists(&self, id: &Uuid) -> Result<bool> {
+        self.db
+            .contains_key(id.as_bytes())
+            .context("Failed to check for todo")
+    }
+
     pub fn get(&self, id: &Uuid) -> Result<Option<Todo>> {
         let key = id.as_bytes();
         match self.db.get(key).context("Failed to get todo")? {
@@ -38,101 +237,1734 @@ impl TodoDb {
         }
     }
 
+    /// Looks up several todos with a single range scan between the smallest
+    /// and largest id, which is cheap when the ids were created close together.
+    /// The result pairs each of `ids` with its todo, in the same order.
+    pub fn get_many(&self, ids: &[Uuid]) -> Result<Vec<(Uuid, Option<Todo>)>> {
+        let wanted: BTreeSet<Uuid> = ids.iter().copied().collect();
+        let (Some(first), Some(last)) = (wanted.first(), wanted.last()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut found = HashMap::with_capacity(wanted.len());
+        for item in self
+            .db
+            .range(first.as_bytes().as_slice()..=last.as_bytes().as_slice())
+        {
+            let (key, value) = item.context("Failed to iterate over todos")?;
+            let Ok(id) = Uuid::from_slice(&key) else {
+                continue;
+            };
+            // Only decode the records that were asked for
+            if wanted.contains(&id) {
+                found.insert(id, decode_todo(&value)?);
+            }
+        }
+        Ok(ids.iter().map(|id| (*id, found.get(id).cloned())).collect())
+    }
+
+    /// Every todo, pinned ones first and each group newest first by creation
+    /// time, falling back to the id for equal times
     pub fn get_all(&self) -> Result<Vec<Todo>> {
-        let mut todos = Vec::new();
-        let config = bincode::config::standard();
+        let mut todos = self.iter_all().collect::<Result<Vec<_>>>()?;
+        todos.sort_by_key(listing_order);
+        Ok(todos)
+    }
+
+    /// Like [`get_all`](Self::get_all), but soonest due first with undated todos last
+    pub fn get_all_by_due_date(&self) -> Result<Vec<Todo>> {
+        let mut todos = self.get_all()?;
+        // Stable, so pinned todos stay first and equal dates stay newest first
+        todos.sort_by_key(|todo| {
+            (
+                !todo.pinned,
+                todo.due_date.unwrap_or(DateTime::<Utc>::MAX_UTC),
+            )
+        });
+        Ok(todos)
+    }
+
+    /// Up to `limit` todos matching `query` that follow the todo `after` in
+    /// the given order, or the first `limit` matches when `after` is `None`.
+    /// The scan goes on until the page is full, so filtered pages only come
+    /// up short at the end; the paging fields of `query` are ignored.
+    /// Creation order is scanned straight from sled, so a deleted cursor
+    /// still works there; the other orders need `after` to exist.
+    pub fn get_page(
+        &self,
+        after: Option<&Uuid>,
+        limit: usize,
+        sort: SortBy,
+        dir: SortDirection,
+        query: &TodoQuery,
+    ) -> Result<Vec<Todo>> {
+        if sort != SortBy::CreatedAt {
+            let todos = self.get_all_sorted(sort, dir)?;
+            let start = match after {
+                Some(after) => {
+                    todos
+                        .iter()
+                        .position(|todo| todo.id == *after)
+                        .with_context(|| format!("Cursor {} does not exist", after))?
+                        + 1
+                }
+                None => 0,
+            };
+            return Ok(todos
+                .into_iter()
+                .skip(start)
+                .filter(|todo| query.matches(todo))
+                .take(limit)
+                .collect());
+        }
+
+        // Keys are UUIDv7, so key order is creation order
+        let ascending = dir == SortDirection::Asc;
+        let range = match after {
+            Some(after) if ascending => self
+                .db
+                .range((Bound::Excluded(*after.as_bytes()), Bound::Unbounded)),
+            Some(after) => self.db.range(..*after.as_bytes()),
+            None => self.db.iter(),
+        };
+        let items: Box<dyn Iterator<Item = sled::Result<(sled::IVec, sled::IVec)>>> = if ascending {
+            Box::new(range)
+        } else {
+            Box::new(range.rev())
+        };
+        let mut page = Vec::new();
+        for item in items {
+            if page.len() == limit {
+                break;
+            }
+            let (_key, value) = item.context("Failed to iterate over todos")?;
+            let todo = decode_todo(&value)?;
+            if query.matches(&todo) {
+                page.push(todo);
+            }
+        }
+        Ok(page)
+    }
+
+    /// Lazily iterates all todos, oldest first
+    pub fn iter_all(&self) -> impl Iterator<Item = Result<Todo>> + '_ {
+        self.db.iter().map(|item| {
+            let (_key, value) = item.context("Failed to iterate over todos")?;
+            decode_todo(&value)
+        })
+    }
+
+    /// Lazily iterates todos whose keys fall within `[start_key, end_key)`.
+    /// Keys are UUIDv7 bytes, so id ranges are also creation time ranges.
+    pub fn scan_range(
+        &self,
+        start_key: &[u8],
+        end_key: &[u8],
+    ) -> impl Iterator<Item = Result<Todo>> + '_ {
+        self.db.range(start_key..end_key).map(|item| {
+            let (_key, value) = item.context("Failed to iterate over todos")?;
+            decode_todo(&value)
+        })
+    }
+
+    /// Subscribes to inserts, updates and removals of a single todo
+    pub fn watch_todo(&self, id: &Uuid) -> sled::Subscriber {
+        self.db.watch_prefix(id.as_bytes())
+    }
+
+    /// Number of stored todos. Note that sled computes `len` by scanning the
+    /// tree, but without decoding any records.
+    pub fn count(&self) -> Result<u64> {
+        Ok(self.db.len() as u64)
+    }
+
+    /// Number of todos matching `predicate`. Every record is decoded, but
+    /// only the count is kept.
+    pub fn count_where(&self, predicate: impl Fn(&Todo) -> bool) -> Result<u64> {
+        let mut count = 0;
+        for todo in self.iter_all() {
+            if predicate(&todo?) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Whether a todo with the given title exists, ignoring case
+    pub fn contains_title(&self, title: &str) -> Result<bool> {
+        let title = title.to_lowercase();
         for item in self.db.iter() {
             let (_key, value) = item.context("Failed to iterate over todos")?;
-            let (todo, _): (Todo, _) = bincode::serde::decode_from_slice(&value, config)
-                .context("Failed to deserialize todo")?;
-            todos.push(todo);
+            if decode_todo(&value)?.title.to_lowercase() == title {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Picks a random incomplete todo, weighting High priority 3x, Medium 2x
+    /// and Low 1x. Returns `None` when everything is done.
+    pub fn get_random_weighted(&self) -> Result<Option<Todo>> {
+        let mut rng = rand::thread_rng();
+        let mut total_weight = 0;
+        let mut chosen = None;
+        // Weighted reservoir sampling: one pass, no need to hold every todo
+        for item in self.db.iter() {
+            let (_key, value) = item.context("Failed to iterate over todos")?;
+            let todo = decode_todo(&value)?;
+            if todo.completed {
+                continue;
+            }
+            let weight = u32::from(todo.priority.to_numeric()) + 1;
+            total_weight += weight;
+            if rng.gen_range(0..total_weight) < weight {
+                chosen = Some(todo);
+            }
+        }
+        Ok(chosen)
+    }
+
+    /// Returns pinned todos, ordered like [`get_all`](Self::get_all)
+    pub fn get_pinned(&self) -> Result<Vec<Todo>> {
+        let mut todos = Vec::new();
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if todo.pinned {
+                todos.push(todo);
+            }
         }
-        // Sort by created_at descending (newest first)
-        todos.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        todos.sort_by_key(listing_order);
         Ok(todos)
     }
 
-    pub fn update(&self, todo: &Todo) -> Result<()> {
-        let key = todo.id.as_bytes();
+    /// Todos carrying `tag`, ordered like [`get_all`](Self::get_all)
+    pub fn get_by_tag(&self, tag: &str) -> Result<Vec<Todo>> {
+        self.query(&crate::TodoQuery {
+            tag: Some(tag.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// Every tag in use, sorted and without duplicates
+    pub fn all_tags(&self) -> Result<Vec<String>> {
+        let mut tags = BTreeSet::new();
+        for todo in self.iter_all() {
+            tags.extend(todo?.tags);
+        }
+        Ok(tags.into_iter().collect())
+    }
+
+    /// Up to `limit` other todos sharing at least one tag with the todo `id`,
+    /// with the number of shared tags, most shared first. Empty if there is
+    /// no todo `id` or it has no tags.
+    pub fn get_related(&self, id: &Uuid, limit: usize) -> Result<Vec<(Todo, u64)>> {
+        let Some(target) = self.get(id)? else {
+            return Ok(Vec::new());
+        };
+        let mut related = Vec::new();
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if todo.id == target.id {
+                continue;
+            }
+            let shared = todo
+                .tags
+                .iter()
+                .filter(|tag| target.tags.contains(tag))
+                .count() as u64;
+            if shared > 0 {
+                related.push((todo, shared));
+            }
+        }
+        // Stable, so ties stay oldest first
+        related.sort_by(|a, b| b.1.cmp(&a.1));
+        related.truncate(limit);
+        Ok(related)
+    }
+
+    /// Sums the estimates of all todos that are not completed yet
+    pub fn total_estimated_hours(&self) -> Result<f32> {
+        Ok(self.summary()?.total_estimated_hours)
+    }
+
+    /// Counts and estimate totals from a single pass over all todos
+    pub fn summary(&self) -> Result<Summary> {
+        let mut summary = Summary::default();
+        for todo in self.iter_all() {
+            summary.add(&todo?);
+        }
+        Ok(summary)
+    }
+
+    /// Aggregates creations and completions in `[from, to)` into buckets
+    pub fn timeline_stats(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        granularity: Granularity,
+    ) -> Result<Vec<TimelineBucket>> {
+        let todos = self.get_all()?;
+        Ok(crate::stats::timeline(&todos, from, to, granularity))
+    }
+
+    /// Iterates todos from highest to lowest priority, newest first within a
+    /// priority, by walking the priority index instead of sorting in memory.
+    pub fn iter_by_priority_descending(&self) -> impl Iterator<Item = Result<Todo>> + '_ {
+        self.priority_index.iter().rev().map(move |item| {
+            let (key, _) = item.context("Failed to iterate over priority index")?;
+            let id = Uuid::from_slice(&key[PRIORITY_INDEX_ID_OFFSET..])
+                .context("Invalid priority index key")?;
+            self.get(&id)?
+                .with_context(|| format!("Priority index references missing todo {}", id))
+        })
+    }
+
+    /// Stores `todo` over its previous record and returns what was stored:
+    /// `todo` with the version one past the stored one, however many changes
+    /// were made to it
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn update(&self, todo: &Todo) -> Result<Todo> {
+        self.write_update(todo, None)
+    }
+
+    /// Like [`update`](Self::update), but only while the stored todo is still
+    /// at version `expected`; otherwise fails with a [`VersionConflict`]. The
+    /// check and the write are one transaction, so concurrent updates from
+    /// the same version cannot both succeed.
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn update_if_version(&self, todo: &Todo, expected: u64) -> Result<Todo> {
+        self.write_update(todo, Some(expected))
+    }
+
+    fn write_update(&self, todo: &Todo, expected: Option<u64>) -> Result<Todo> {
+        let stored = self.write_with(&todo.id, |previous| {
+            if let Some(expected) = expected {
+                let Some(previous) = previous else {
+                    bail!("Todo with id {} not found", todo.id);
+                };
+                if previous.version != expected {
+                    return Err(VersionConflict {
+                        expected,
+                        current: previous.version,
+                    }
+                    .into());
+                }
+            }
+            Ok(Some(todo.clone()))
+        })?;
+        Ok(stored.expect("updates always write"))
+    }
+
+    /// Stores what `change` makes of the stored todo `id`, if any, within one
+    /// transaction, so nothing can be written between the read and the write.
+    /// Nothing is written when `change` returns `None` or fails.
+    fn write_with(
+        &self,
+        id: &Uuid,
+        change: impl Fn(Option<&Todo>) -> Result<Option<Todo>>,
+    ) -> Result<Option<Todo>> {
+        let key = id.as_bytes();
         let config = bincode::config::standard();
-        let value =
-            bincode::serde::encode_to_vec(todo, config).context("Failed to serialize todo")?;
-        self.db
-            .insert(key, value)
-            .context("Failed to update todo")?;
+        let abort = |err: anyhow::Error| ConflictableTransactionError::Abort(err);
+        let stored = (&*self.db, &self.priority_index)
+            .transaction(|(db, priority_index)| {
+                let previous = match db.get(key)? {
+                    Some(bytes) => Some(decode_todo(&bytes).map_err(abort)?),
+                    None => None,
+                };
+                let Some(mut stored) = change(previous.as_ref()).map_err(abort)? else {
+                    return Ok(None);
+                };
+                if let Some(previous) = &previous {
+                    stored.version = previous.version + 1;
+                    priority_index.remove(priority_index_key(previous))?;
+                }
+                let value = bincode::serde::encode_to_vec(&stored, config).map_err(|err| {
+                    abort(anyhow::Error::new(err).context("Failed to serialize todo"))
+                })?;
+                db.insert(key, value)?;
+                priority_index.insert(priority_index_key(&stored), INDEX_VALUE)?;
+                Ok(Some(stored))
+            })
+            .map_err(|err| match err {
+                TransactionError::Abort(err) => err,
+                TransactionError::Storage(err) => {
+                    anyhow::Error::new(err).context("Failed to update todo")
+                }
+            })?;
+        if let Some(stored) = &stored {
+            self.db.flush().context("Failed to flush database")?;
+            self.notify_change();
+            run_hooks(&self.on_update, stored);
+        }
+        Ok(stored)
+    }
+
+    /// Incomplete todos due within `[from, to)`
+    pub fn get_due_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Todo>> {
+        let mut todos = Vec::new();
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if !todo.completed && todo.due_date.is_some_and(|due| from <= due && due < to) {
+                todos.push(todo);
+            }
+        }
+        Ok(todos)
+    }
+
+    /// Todos created within `[from, to)`, oldest first. Records migrated from
+    /// before ids were time-ordered rule out a key range scan, so every todo
+    /// is checked.
+    pub fn get_created_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Todo>> {
+        let mut todos = Vec::new();
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if from <= todo.created_at && todo.created_at < to {
+                todos.push(todo);
+            }
+        }
+        todos.sort_by_key(|todo| todo.created_at);
+        Ok(todos)
+    }
+
+    /// Incomplete todos whose due date has passed, most overdue first
+    pub fn get_overdue(&self) -> Result<Vec<Todo>> {
+        let mut todos = Vec::new();
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if todo.is_overdue() {
+                todos.push(todo);
+            }
+        }
+        todos.sort_by_key(|todo| todo.due_date);
+        Ok(todos)
+    }
+
+    /// Incomplete todos due on the current UTC day, soonest first
+    pub fn get_due_today(&self) -> Result<Vec<Todo>> {
+        let today = Utc::now().date_naive();
+        self.get_due_on_days(today, today + chrono::Days::new(1))
+    }
+
+    /// Incomplete todos due in the current Monday-to-Sunday UTC week,
+    /// soonest first; includes those already overdue since Monday
+    pub fn get_due_this_week(&self) -> Result<Vec<Todo>> {
+        let monday = Utc::now()
+            .date_naive()
+            .week(chrono::Weekday::Mon)
+            .first_day();
+        self.get_due_on_days(monday, monday + chrono::Days::new(7))
+    }
+
+    fn get_due_on_days(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Todo>> {
+        let midnight = |day: NaiveDate| day.and_time(NaiveTime::MIN).and_utc();
+        let mut todos = self.get_due_between(midnight(from), midnight(to))?;
+        todos.sort_by_key(|todo| todo.due_date);
+        Ok(todos)
+    }
+
+    /// Stores `preference`, replacing any earlier one for the same user
+    pub fn set_notification_preference(&self, preference: &NotificationPreference) -> Result<()> {
+        let config = bincode::config::standard();
+        let value = bincode::serde::encode_to_vec(preference, config)
+            .context("Failed to serialize notification preference")?;
+        self.notification_prefs
+            .insert(preference.user.as_bytes(), value)
+            .context("Failed to insert notification preference")?;
         self.db.flush().context("Failed to flush database")?;
         Ok(())
     }
 
+    pub fn notification_preferences(&self) -> Result<Vec<NotificationPreference>> {
+        let config = bincode::config::standard();
+        self.notification_prefs
+            .iter()
+            .map(|item| {
+                let (_key, value) =
+                    item.context("Failed to iterate over notification preferences")?;
+                let (preference, _) = bincode::serde::decode_from_slice(&value, config)
+                    .context("Failed to deserialize notification preference")?;
+                Ok(preference)
+            })
+            .collect()
+    }
+
+    /// Merges `patch` into the todo's metadata and returns the merged value,
+    /// or `None` when there is no such todo. The merge happens inside the
+    /// write's transaction, so concurrent patches can't drop each other's
+    /// keys. Fails with [`MetadataTooLarge`] when the merged value serializes
+    /// to more than `max_bytes`.
+    pub fn update_metadata(
+        &self,
+        id: &Uuid,
+        patch: Value,
+        max_bytes: usize,
+    ) -> Result<Option<Value>> {
+        let stored = self.write_with(id, |previous| {
+            let Some(previous) = previous else {
+                return Ok(None);
+            };
+            let mut todo = previous.clone();
+            todo.merge_metadata(patch.clone());
+            MetadataTooLarge::check(&todo.metadata, max_bytes)?;
+            Ok(Some(todo))
+        })?;
+        Ok(stored.map(|todo| todo.metadata))
+    }
+
+    #[must_use = "check whether the database operation succeeded"]
     pub fn delete(&self, id: &Uuid) -> Result<bool> {
         let key = id.as_bytes();
-        let existed = self
-            .db
-            .remove(key)
-            .context("Failed to delete todo")?
-            .is_some();
+        let removed = match self.db.remove(key).context("Failed to delete todo")? {
+            Some(bytes) => {
+                let config = bincode::config::standard();
+                let (old, _): (Todo, _) = bincode::serde::decode_from_slice(&bytes, config)
+                    .context("Failed to deserialize todo")?;
+                self.priority_index
+                    .remove(priority_index_key(&old))
+                    .context("Failed to update priority index")?;
+                Some(old)
+            }
+            None => None,
+        };
+        self.db.flush().context("Failed to flush database")?;
+        if let Some(old) = &removed {
+            self.notify_change();
+            run_hooks(&self.on_delete, old);
+        }
+        Ok(removed.is_some())
+    }
+
+    /// Inserts all `todos` atomically, together with their index entries,
+    /// and flushes once
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn insert_batch(&self, todos: &[Todo]) -> Result<()> {
+        let config = bincode::config::standard();
+        let mut records = Batch::default();
+        let mut index = Batch::default();
+        for todo in todos {
+            let value =
+                bincode::serde::encode_to_vec(todo, config).context("Failed to serialize todo")?;
+            records.insert(todo.id.as_bytes(), value);
+            index.insert(priority_index_key(todo), INDEX_VALUE);
+        }
+        (&*self.db, &self.priority_index)
+            .transaction(|(db, priority_index)| {
+                db.apply_batch(&records)?;
+                priority_index.apply_batch(&index)?;
+                Ok(())
+            })
+            .map_err(|err| transaction_error(err, "Failed to insert todos"))?;
         self.db.flush().context("Failed to flush database")?;
-        Ok(existed)
+        if !todos.is_empty() {
+            self.notify_change();
+        }
+        for todo in todos {
+            run_hooks(&self.on_insert, todo);
+        }
+        Ok(())
+    }
+
+    /// Deletes all `ids` atomically and flushes once. Returns whether each
+    /// id, in order, was present.
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn delete_batch(&self, ids: &[Uuid]) -> Result<Vec<bool>> {
+        let config = bincode::config::standard();
+        let removed: Vec<Option<Todo>> = (&*self.db, &self.priority_index)
+            .transaction(|(db, priority_index)| {
+                let mut removed = Vec::with_capacity(ids.len());
+                for id in ids {
+                    let old = match db.remove(id.as_bytes())? {
+                        Some(bytes) => {
+                            let (old, _): (Todo, _) = bincode::serde::decode_from_slice(
+                                &bytes, config,
+                            )
+                            .map_err(|err| {
+                                ConflictableTransactionError::Abort(format!(
+                                    "Failed to deserialize todo {}: {}",
+                                    id, err
+                                ))
+                            })?;
+                            priority_index.remove(priority_index_key(&old))?;
+                            Some(old)
+                        }
+                        None => None,
+                    };
+                    removed.push(old);
+                }
+                Ok(removed)
+            })
+            .map_err(|err| transaction_error(err, "Failed to delete todos"))?;
+        self.db.flush().context("Failed to flush database")?;
+        if removed.iter().any(Option::is_some) {
+            self.notify_change();
+        }
+        for old in removed.iter().flatten() {
+            run_hooks(&self.on_delete, old);
+        }
+        Ok(removed.iter().map(Option::is_some).collect())
+    }
+
+    /// Deletes every completed todo in one transaction and flushes once.
+    /// Returns how many were deleted.
+    ///
+    /// Each todo found completed by the scan is read again inside the
+    /// transaction, so one reopened or edited in the meantime is kept, and
+    /// its index entry is taken from the record being deleted.
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn delete_completed(&self) -> Result<u64> {
+        let mut candidates = Vec::new();
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if todo.completed {
+                candidates.push(todo.id);
+            }
+        }
+        let abort = |err: anyhow::Error| ConflictableTransactionError::Abort(err);
+        let deleted = (&*self.db, &self.priority_index)
+            .transaction(|(db, priority_index)| {
+                let mut deleted = Vec::new();
+                for id in &candidates {
+                    let Some(bytes) = db.get(id.as_bytes())? else {
+                        continue;
+                    };
+                    let todo = decode_todo(&bytes).map_err(abort)?;
+                    if todo.completed {
+                        db.remove(id.as_bytes())?;
+                        priority_index.remove(priority_index_key(&todo))?;
+                        deleted.push(todo);
+                    }
+                }
+                Ok(deleted)
+            })
+            .map_err(|err| match err {
+                TransactionError::Abort(err) => err,
+                TransactionError::Storage(err) => {
+                    anyhow::Error::new(err).context("Failed to delete completed todos")
+                }
+            })?;
+        if deleted.is_empty() {
+            return Ok(0);
+        }
+        self.db.flush().context("Failed to flush database")?;
+        self.notify_change();
+        for todo in &deleted {
+            run_hooks(&self.on_delete, todo);
+        }
+        Ok(deleted.len() as u64)
     }
 
     pub fn clear_all(&self) -> Result<()> {
         self.db.clear().context("Failed to clear database")?;
+        self.priority_index
+            .clear()
+            .context("Failed to clear priority index")?;
         self.db.flush().context("Failed to flush database")?;
+        self.notify_change();
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::Priority;
+    /// Checks that the database can still be read by fetching the schema
+    /// version, which every opened database has. Nothing is written, so
+    /// health probes can call it as often as they like.
+    pub fn ping(&self) -> Result<()> {
+        let meta = self
+            .db
+            .open_tree(crate::migration::META_TREE)
+            .context("Failed to open meta tree")?;
+        meta.get(crate::migration::SCHEMA_VERSION_KEY)
+            .context("Failed to read from database")?
+            .context("Schema version is missing")?;
+        Ok(())
+    }
 
-    #[test]
-    fn test_todo_crud() {
-        let temp_dir = tempfile::tempdir().unwrap();
-        let db = TodoDb::new(temp_dir.path()).unwrap();
+    /// Approximate size of the database directory in bytes
+    pub fn size_on_disk(&self) -> Result<u64> {
+        dir_size(&self.path).context("Failed to measure database directory")
+    }
 
-        // Create
-        let todo = Todo::new(
-            "Test todo".to_string(),
-            Some("Description".to_string()),
-            None,
-            Priority::High,
-        );
-        let id = todo.id;
-        db.insert(&todo).unwrap();
+    /// Writes every tree, including indexes and preferences, to the file
+    /// `dest` in sled's export format, encoded with bincode
+    pub fn snapshot(&self, dest: &Path) -> Result<()> {
+        let collections: Vec<SnapshotCollection> = self
+            .db
+            .export()
+            .into_iter()
+            .map(|(kind, name, items)| (kind, name, items.collect()))
+            .collect();
+        let file = std::fs::File::create(dest)
+            .with_context(|| format!("Failed to create snapshot {}", dest.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        bincode::encode_into_std_write(&collections, &mut writer, bincode::config::standard())
+            .context("Failed to write snapshot")?;
+        writer.flush().context("Failed to write snapshot")?;
+        Ok(())
+    }
 
-        // Read
-        let retrieved = db.get(&id).unwrap().unwrap();
-        assert_eq!(retrieved.title, "Test todo");
-        assert_eq!(retrieved.priority, Priority::High);
+    /// Replaces the whole database with the snapshot at `src`, upgrading
+    /// records from older schema versions. Returns the number of todos
+    /// restored.
+    ///
+    /// The snapshot is checked before anything is cleared, so a corrupt one
+    /// leaves the database as it was. Trees are written one by one rather
+    /// than through sled's `import`, which panics on malformed input.
+    pub fn restore_from_snapshot(&self, src: &Path) -> Result<u64> {
+        let file = std::fs::File::open(src)
+            .with_context(|| format!("Failed to open snapshot {}", src.display()))?;
+        let collections: Vec<SnapshotCollection> = bincode::decode_from_std_read(
+            &mut std::io::BufReader::new(file),
+            bincode::config::standard(),
+        )
+        .context("Failed to read snapshot")?;
+        for (kind, name, items) in &collections {
+            let name = String::from_utf8_lossy(name);
+            if kind.as_slice() != b"tree" {
+                bail!(
+                    "Snapshot collection {} has unknown type {:?}",
+                    name,
+                    String::from_utf8_lossy(kind)
+                );
+            }
+            if items.iter().any(|item| item.len() != 2) {
+                bail!("Snapshot collection {} has a malformed record", name);
+            }
+        }
 
-        // Update
-        let mut updated_todo = retrieved.clone();
-        updated_todo.mark_completed();
-        db.update(&updated_todo).unwrap();
+        for name in self.db.tree_names() {
+            self.db
+                .open_tree(&name)
+                .and_then(|tree| tree.clear())
+                .context("Failed to clear tree before restore")?;
+        }
+        for (_kind, name, items) in collections {
+            let mut batch = Batch::default();
+            for item in items {
+                let [key, value]: [Vec<u8>; 2] = item.try_into().expect("checked above");
+                batch.insert(key, value);
+            }
+            self.db
+                .open_tree(&name)
+                .and_then(|tree| tree.apply_batch(batch))
+                .context("Failed to restore tree")?;
+        }
+        self.db.flush().context("Failed to flush database")?;
+        crate::migration::upgrade(&self.db)?;
+        self.notify_change();
+        self.count()
+    }
 
-        let retrieved_again = db.get(&id).unwrap().unwrap();
-        assert!(retrieved_again.completed);
+    /// Returns a counter that's bumped after every successful write, for
+    /// callers that only need to know that something changed. The counter
+    /// wraps around at `u64::MAX`.
+    pub fn watch_all(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
 
-        // Delete
-        let deleted = db.delete(&id).unwrap();
-        assert!(deleted);
-        assert!(db.get(&id).unwrap().is_none());
+    fn notify_change(&self) {
+        // `send_modify` also works while nobody is subscribed
+        self.changes
+            .send_modify(|counter| *counter = counter.wrapping_add(1));
     }
 
-    #[test]
-    fn test_get_all() {
-        let temp_dir = tempfile::tempdir().unwrap();
-        let db = TodoDb::new(temp_dir.path()).unwrap();
+    /// Flushes outstanding writes and closes this handle, reporting flush errors
+    pub fn close(self) -> Result<()> {
+        self.db.flush().context("Failed to flush database")?;
+        Ok(())
+    }
 
-        let todo1 = Todo::new("Todo 1".to_string(), None, None, Priority::Low);
-        let todo2 = Todo::new("Todo 2".to_string(), None, None, Priority::Medium);
+    /// Rebuilds every secondary index from the stored todos, e.g. after a
+    /// crash left one out of sync. Writes that race with the rebuild can
+    /// leave stale index entries behind; run it again once they've settled.
+    pub fn reindex(&self) -> Result<ReindexReport> {
+        let records_processed = self.rebuild_priority_index()?;
+        Ok(ReindexReport {
+            records_processed,
+            indexes_rebuilt: vec![PRIORITY_INDEX_TREE.to_string()],
+        })
+    }
 
-        db.insert(&todo1).unwrap();
-        db.insert(&todo2).unwrap();
+    fn rebuild_priority_index(&self) -> Result<u64> {
+        self.priority_index
+            .clear()
+            .context("Failed to clear priority index")?;
+        let config = bincode::config::standard();
+        let mut records = 0;
+        for item in self.db.iter() {
+            let (_key, value) = item.context("Failed to iterate over todos")?;
+            let (todo, _): (Todo, _) = bincode::serde::decode_from_slice(&value, config)
+                .context("Failed to deserialize todo")?;
+            self.priority_index
+                .insert(priority_index_key(&todo), INDEX_VALUE)
+                .context("Failed to update priority index")?;
+            records += 1;
+        }
+        self.db.flush().context("Failed to flush database")?;
+        Ok(records)
+    }
+}
 
-        let all_todos = db.get_all().unwrap();
-        assert_eq!(all_todos.len(), 2);
+impl fmt::Debug for TodoDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TodoDb")
+            .field("path", &self.path)
+            .field("record_count", &self.db.len())
+            .field("was_recovered", &self.db.was_recovered())
+            .finish()
+    }
+}
+
+/// Diagnostic snapshot of the store, not its contents
+impl Serialize for TodoDb {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let size_on_disk_bytes = self.size_on_disk().map_err(S::Error::custom)?;
+        let mut state = serializer.serialize_struct("TodoDb", 3)?;
+        state.serialize_field("record_count", &self.db.len())?;
+        state.serialize_field("size_on_disk_bytes", &size_on_disk_bytes)?;
+        state.serialize_field("was_recovered", &self.db.was_recovered())?;
+        state.end()
+    }
+}
+
+fn decode_todo(bytes: &[u8]) -> Result<Todo> {
+    let config = bincode::config::standard();
+    let (todo, _): (Todo, _) =
+        bincode::serde::decode_from_slice(bytes, config).context("Failed to deserialize todo")?;
+    Ok(todo)
+}
+
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        total += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(total)
+}
+
+/// Converts a failed multi-tree transaction, which aborts with a message
+pub(crate) fn transaction_error(err: TransactionError<String>, context: &str) -> anyhow::Error {
+    match err {
+        TransactionError::Abort(message) => anyhow::anyhow!("{}: {}", context, message),
+        TransactionError::Storage(err) => anyhow::Error::new(err).context(context.to_string()),
+    }
+}
+
+/// Sort key of listings: pinned todos first, then newest first by creation
+/// time. Ids only break ties, since imported and migrated todos can have a
+/// creation time that their id does not reflect.
+pub(crate) fn listing_order(todo: &Todo) -> (bool, Reverse<DateTime<Utc>>, Reverse<Uuid>) {
+    (!todo.pinned, Reverse(todo.created_at), Reverse(todo.id))
+}
+
+fn run_hooks(hooks: &[Hook], todo: &Todo) {
+    for hook in hooks {
+        hook(todo);
+    }
+}
+
+const PRIORITY_INDEX_ID_OFFSET: usize = 1 + 8;
+/// Index entries carry all their information in the key
+const INDEX_VALUE: &[u8] = &[];
+
+fn priority_index_key(todo: &Todo) -> Vec<u8> {
+    let mut key = Vec::with_capacity(PRIORITY_INDEX_ID_OFFSET + 16);
+    key.push(todo.priority.to_numeric());
+    // Flipping the sign bit makes the big-endian bytes sort like the timestamp
+    let created_at = (todo.created_at.timestamp_micros() as u64) ^ (1 << 63);
+    key.extend_from_slice(&created_at.to_be_bytes());
+    key.extend_from_slice(todo.id.as_bytes());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_update_if_version_rejects_stale_writes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let todo = Todo::new("Shared".to_string(), None, None, Priority::Low);
+        db.insert(&todo).unwrap();
+
+        // Two clients edit the same version; only the first write lands
+        let mut first = todo.clone();
+        first.set_pinned(true);
+        first.mark_completed();
+        assert_eq!(db.update_if_version(&first, 1).unwrap().version, 2);
+
+        let mut second = todo.clone();
+        second.title = "Overwritten".to_string();
+        let err = db.update_if_version(&second, 1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<VersionConflict>(),
+            Some(&VersionConflict {
+                expected: 1,
+                current: 2
+            })
+        );
+        assert_eq!(db.get(&todo.id).unwrap().unwrap().title, "Shared");
+    }
+
+    #[test]
+    fn test_count() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let todos: Vec<Todo> = (0..5)
+            .map(|i| Todo::new(format!("Todo {}", i), None, None, Priority::Low))
+            .collect();
+        for todo in &todos {
+            db.insert(todo).unwrap();
+        }
+        assert_eq!(db.count().unwrap(), 5);
+
+        db.delete(&todos[0].id).unwrap();
+        assert_eq!(db.count().unwrap(), 4);
+
+        let mut done = todos[1].clone();
+        done.mark_completed();
+        db.update(&done).unwrap();
+        assert_eq!(db.count_where(|todo| todo.completed).unwrap(), 1);
+        assert_eq!(db.count_where(|todo| !todo.completed).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_summary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut open = Todo::new("Open".to_string(), None, None, Priority::Low);
+        open.estimate_hours = Some(2.0);
+        let mut overdue = Todo::new(
+            "Overdue".to_string(),
+            None,
+            Some(Utc::now() - chrono::Duration::days(1)),
+            Priority::Low,
+        );
+        overdue.estimate_hours = Some(0.5);
+        let mut done = Todo::new("Done".to_string(), None, None, Priority::Low);
+        done.estimate_hours = Some(8.0);
+        done.mark_completed();
+        for todo in [&open, &overdue, &done] {
+            db.insert(todo).unwrap();
+        }
+
+        let summary = db.summary().unwrap();
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.completed, 1);
+        assert_eq!(summary.overdue, 1);
+        assert_eq!(summary.total_estimated_hours, 2.5);
+    }
+
+    #[test]
+    fn test_insert_and_delete_batch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let todos: Vec<Todo> = (0..3)
+            .map(|i| Todo::new(format!("Bulk {}", i), None, None, Priority::Medium))
+            .collect();
+        db.insert_batch(&todos).unwrap();
+        assert_eq!(db.count().unwrap(), 3);
+        assert_eq!(db.iter_by_priority_descending().count(), 3);
+
+        let missing = Uuid::new_v4();
+        let existed = db
+            .delete_batch(&[todos[0].id, missing, todos[2].id])
+            .unwrap();
+        assert_eq!(existed, vec![true, false, true]);
+        assert_eq!(db.count().unwrap(), 1);
+        let remaining: Vec<Todo> = db
+            .iter_by_priority_descending()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(remaining, vec![todos[1].clone()]);
+    }
+
+    #[test]
+    fn test_delete_completed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let open = Todo::new("Open".to_string(), None, None, Priority::Low);
+        let mut done = Todo::new("Done".to_string(), None, None, Priority::High);
+        done.mark_completed();
+        db.insert_batch(&[open.clone(), done]).unwrap();
+
+        assert_eq!(db.delete_completed().unwrap(), 1);
+        assert_eq!(db.get_all().unwrap(), vec![open]);
+        assert_eq!(db.iter_by_priority_descending().count(), 1);
+        assert_eq!(db.delete_completed().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_tags() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut errand = Todo::new("Errand".to_string(), None, None, Priority::Low);
+        errand.set_tags(vec!["home".to_string(), "urgent".to_string()]);
+        let mut report = Todo::new("Report".to_string(), None, None, Priority::High);
+        report.set_tags(vec!["work".to_string(), "urgent".to_string()]);
+        let untagged = Todo::new("Untagged".to_string(), None, None, Priority::Low);
+        for todo in [&errand, &report, &untagged] {
+            db.insert(todo).unwrap();
+        }
+
+        let urgent: Vec<String> = db
+            .get_by_tag("urgent")
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.title)
+            .collect();
+        assert_eq!(urgent.len(), 2);
+        assert!(urgent.contains(&"Errand".to_string()) && urgent.contains(&"Report".to_string()));
+        assert!(db.get_by_tag("Urgent").unwrap().is_empty());
+        assert_eq!(db.all_tags().unwrap(), vec!["home", "urgent", "work"]);
+    }
+
+    #[test]
+    fn test_get_related() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut target = Todo::new("Target".to_string(), None, None, Priority::Low);
+        target.set_tags(vec!["home".to_string(), "urgent".to_string()]);
+        let mut one = Todo::new("One".to_string(), None, None, Priority::Low);
+        one.set_tags(vec!["urgent".to_string(), "work".to_string()]);
+        let mut both = Todo::new("Both".to_string(), None, None, Priority::Low);
+        both.set_tags(vec!["urgent".to_string(), "home".to_string()]);
+        let mut other = Todo::new("Other".to_string(), None, None, Priority::Low);
+        other.set_tags(vec!["work".to_string()]);
+        for todo in [&target, &one, &both, &other] {
+            db.insert(todo).unwrap();
+        }
+
+        let related: Vec<(String, u64)> = db
+            .get_related(&target.id, 10)
+            .unwrap()
+            .into_iter()
+            .map(|(todo, shared)| (todo.title, shared))
+            .collect();
+        assert_eq!(
+            related,
+            vec![("Both".to_string(), 2), ("One".to_string(), 1)]
+        );
+        assert_eq!(db.get_related(&target.id, 1).unwrap().len(), 1);
+        assert_eq!(db.get_related(&other.id, 10).unwrap()[0].0.id, one.id);
+        assert!(db.get_related(&Uuid::new_v4(), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_contains_title() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        db.insert(&Todo::new(
+            "Buy Milk".to_string(),
+            None,
+            None,
+            Priority::Low,
+        ))
+        .unwrap();
+
+        assert!(db.contains_title("buy milk").unwrap());
+        assert!(!db.contains_title("buy bread").unwrap());
+    }
+
+    #[test]
+    fn test_get_random_weighted_skips_completed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        assert!(db.get_random_weighted().unwrap().is_none());
+
+        let mut done = Todo::new("Done".to_string(), None, None, Priority::High);
+        done.mark_completed();
+        db.insert(&done).unwrap();
+        assert!(db.get_random_weighted().unwrap().is_none());
+
+        let open = Todo::new("Open".to_string(), None, None, Priority::Low);
+        db.insert(&open).unwrap();
+        for _ in 0..10 {
+            assert_eq!(db.get_random_weighted().unwrap().unwrap(), open);
+        }
+    }
+
+    #[test]
+    fn test_get_many() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let first = Todo::new("First".to_string(), None, None, Priority::Low);
+        let second = Todo::new("Second".to_string(), None, None, Priority::Low);
+        db.insert(&first).unwrap();
+        db.insert(&second).unwrap();
+
+        let missing = Uuid::new_v4();
+        let found = db.get_many(&[second.id, missing, first.id]).unwrap();
+        let titles: Vec<(Uuid, Option<String>)> = found
+            .into_iter()
+            .map(|(id, todo)| (id, todo.map(|todo| todo.title)))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                (second.id, Some("Second".to_string())),
+                (missing, None),
+                (first.id, Some("First".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_all() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let todo1 = Todo::new("Todo 1".to_string(), None, None, Priority::Low);
+        let todo2 = Todo::new("Todo 2".to_string(), None, None, Priority::Medium);
+
+        db.insert(&todo1).unwrap();
+        db.insert(&todo2).unwrap();
+
+        let all_todos = db.get_all().unwrap();
+        assert_eq!(all_todos.len(), 2);
+    }
+
+    #[test]
+    fn test_get_all_follows_creation_order() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let first = Todo::new("First".to_string(), None, None, Priority::Low);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = Todo::new("Second".to_string(), None, None, Priority::Low);
+        // Its id is the newest, but it was created before the others
+        let mut imported = Todo::new("Imported".to_string(), None, None, Priority::Low);
+        imported.created_at = first.created_at - chrono::Duration::days(1);
+        for todo in [&second, &imported, &first] {
+            db.insert(todo).unwrap();
+        }
+
+        let ids: Vec<Uuid> = db.get_all().unwrap().iter().map(|todo| todo.id).collect();
+        assert_eq!(ids, vec![second.id, first.id, imported.id]);
+    }
+
+    #[test]
+    fn test_scan_range() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut ids = Vec::new();
+        for title in ["One", "Two", "Three"] {
+            let todo = Todo::new(title.to_string(), None, None, Priority::Low);
+            db.insert(&todo).unwrap();
+            ids.push(todo.id);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let titles: Vec<String> = db
+            .scan_range(ids[0].as_bytes(), ids[2].as_bytes())
+            .map(|todo| todo.unwrap().title)
+            .collect();
+        assert_eq!(titles, vec!["One", "Two"]);
+    }
+
+    #[test]
+    fn test_get_all_puts_pinned_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut pinned = Todo::new("Pinned".to_string(), None, None, Priority::Low);
+        pinned.set_pinned(true);
+        db.insert(&pinned).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let newer = Todo::new("Newer".to_string(), None, None, Priority::Low);
+        db.insert(&newer).unwrap();
+
+        let ids: Vec<Uuid> = db.get_all().unwrap().iter().map(|todo| todo.id).collect();
+        assert_eq!(ids, vec![pinned.id, newer.id]);
+    }
+
+    #[test]
+    fn test_get_all_by_due_date_puts_undated_last() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let now = Utc::now();
+
+        let undated = Todo::new("No date".to_string(), None, None, Priority::Low);
+        let tomorrow = Todo::new(
+            "Tomorrow".to_string(),
+            None,
+            Some(now + chrono::Duration::days(1)),
+            Priority::Low,
+        );
+        let next_week = Todo::new(
+            "Next week".to_string(),
+            None,
+            Some(now + chrono::Duration::weeks(1)),
+            Priority::Low,
+        );
+        db.insert(&undated).unwrap();
+        db.insert(&next_week).unwrap();
+        db.insert(&tomorrow).unwrap();
+
+        let titles: Vec<String> = db
+            .get_all_by_due_date()
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.title)
+            .collect();
+        assert_eq!(titles, vec!["Tomorrow", "Next week", "No date"]);
+    }
+
+    #[test]
+    fn test_get_page_follows_cursor() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let mut ids = Vec::new();
+        for title in ["a", "b", "c", "d"] {
+            let todo = Todo::new(title.to_string(), None, None, Priority::Low);
+            db.insert(&todo).unwrap();
+            ids.push(todo.id);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let titles =
+            |page: Vec<Todo>| -> Vec<String> { page.into_iter().map(|todo| todo.title).collect() };
+        let all = TodoQuery::default();
+
+        let page = db
+            .get_page(None, 2, SortBy::CreatedAt, SortDirection::Asc, &all)
+            .unwrap();
+        assert_eq!(titles(page), ["a", "b"]);
+        let page = db
+            .get_page(
+                Some(&ids[1]),
+                2,
+                SortBy::CreatedAt,
+                SortDirection::Asc,
+                &all,
+            )
+            .unwrap();
+        assert_eq!(titles(page), ["c", "d"]);
+        let page = db
+            .get_page(
+                Some(&ids[2]),
+                2,
+                SortBy::CreatedAt,
+                SortDirection::Desc,
+                &all,
+            )
+            .unwrap();
+        assert_eq!(titles(page), ["b", "a"]);
+
+        // A deleted cursor still marks a position in creation order
+        db.delete(&ids[1]).unwrap();
+        let page = db
+            .get_page(
+                Some(&ids[1]),
+                2,
+                SortBy::CreatedAt,
+                SortDirection::Asc,
+                &all,
+            )
+            .unwrap();
+        assert_eq!(titles(page), ["c", "d"]);
+
+        let page = db
+            .get_page(Some(&ids[2]), 2, SortBy::Title, SortDirection::Desc, &all)
+            .unwrap();
+        assert_eq!(titles(page), ["a"]);
+        assert!(
+            db.get_page(Some(&ids[1]), 2, SortBy::Title, SortDirection::Asc, &all)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_get_page_fills_filtered_pages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let mut ids = Vec::new();
+        for (i, title) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            let mut todo = Todo::new(title.to_string(), None, None, Priority::Low);
+            if i % 2 == 1 {
+                todo.mark_completed();
+            }
+            db.insert(&todo).unwrap();
+            ids.push(todo.id);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let completed = TodoQuery {
+            completed: Some(true),
+            ..TodoQuery::default()
+        };
+        let titles =
+            |page: Vec<Todo>| -> Vec<String> { page.into_iter().map(|todo| todo.title).collect() };
+
+        for sort in [SortBy::CreatedAt, SortBy::Title] {
+            let page = db
+                .get_page(None, 2, sort, SortDirection::Asc, &completed)
+                .unwrap();
+            assert_eq!(titles(page), ["b", "d"]);
+            let page = db
+                .get_page(Some(&ids[1]), 1, sort, SortDirection::Asc, &completed)
+                .unwrap();
+            assert_eq!(titles(page), ["d"]);
+        }
+        assert_eq!(db.count_matching(&completed).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_get_pinned_orders_by_creation_time() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let mut recent = Todo::new("Recent".to_string(), None, None, Priority::Low);
+        recent.set_pinned(true);
+        let unpinned = Todo::new("Unpinned".to_string(), None, None, Priority::Low);
+        // An id newer than the creation time, as migrated todos can have
+        let created = Utc.with_ymd_and_hms(2020, 3, 1, 9, 0, 0).unwrap();
+        let mut imported = Todo::new_with_timestamps(
+            "Imported".to_string(),
+            None,
+            None,
+            Priority::Low,
+            created,
+            created,
+        );
+        imported.id = Uuid::now_v7();
+        imported.set_pinned(true);
+        for todo in [&recent, &unpinned, &imported] {
+            db.insert(todo).unwrap();
+        }
+
+        let titles: Vec<String> = db
+            .get_pinned()
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.title)
+            .collect();
+        assert_eq!(titles, ["Recent", "Imported"]);
+    }
+
+    #[test]
+    fn test_iter_by_priority_descending() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let low = Todo::new("Low".to_string(), None, None, Priority::Low);
+        let high = Todo::new("High".to_string(), None, None, Priority::High);
+        let mut medium = Todo::new("Medium".to_string(), None, None, Priority::Low);
+        db.insert(&low).unwrap();
+        db.insert(&high).unwrap();
+        db.insert(&medium).unwrap();
+
+        // Changing the priority must move the todo within the index
+        medium.priority = Priority::Medium;
+        db.update(&medium).unwrap();
+        db.delete(&high.id).unwrap();
+
+        let titles: Vec<String> = db
+            .iter_by_priority_descending()
+            .map(|todo| todo.unwrap().title)
+            .collect();
+        assert_eq!(titles, vec!["Medium", "Low"]);
+    }
+
+    #[test]
+    fn test_reindex_restores_priority_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        for priority in [Priority::Low, Priority::High, Priority::Medium] {
+            db.insert(&Todo::new(format!("{:?}", priority), None, None, priority))
+                .unwrap();
+        }
+        // Simulate an index that lost its entries
+        db.priority_index.clear().unwrap();
+        assert_eq!(db.iter_by_priority_descending().count(), 0);
+
+        let report = db.reindex().unwrap();
+        assert_eq!(report.records_processed, 3);
+        assert_eq!(report.indexes_rebuilt, vec!["priority_index"]);
+
+        let titles: Vec<String> = db
+            .iter_by_priority_descending()
+            .map(|todo| todo.unwrap().title)
+            .collect();
+        assert_eq!(titles, vec!["High", "Medium", "Low"]);
+    }
+
+    #[test]
+    fn test_debug_shows_record_count() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        for i in 0..3 {
+            db.insert(&Todo::new(format!("Todo {}", i), None, None, Priority::Low))
+                .unwrap();
+        }
+
+        let debug = format!("{:?}", db);
+        assert!(debug.contains("record_count: 3"), "{}", debug);
+    }
+
+    #[test]
+    fn test_serialize_reports_diagnostics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        db.insert(&Todo::new("Todo".to_string(), None, None, Priority::Low))
+            .unwrap();
+
+        let info = serde_json::to_value(&db).unwrap();
+        assert_eq!(info["record_count"], 1);
+        assert_eq!(info["was_recovered"], false);
+        assert!(info["size_on_disk_bytes"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_watch_all_counts_writes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let changes = db.watch_all();
+
+        let mut todo = Todo::new("Todo".to_string(), None, None, Priority::Low);
+        db.insert(&todo).unwrap();
+        todo.mark_completed();
+        db.update(&todo).unwrap();
+        db.delete(&todo.id).unwrap();
+        // Deleting a missing todo changes nothing
+        db.delete(&todo.id).unwrap();
+
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(*changes.borrow(), 3);
+    }
+
+    #[test]
+    fn test_size_on_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        db.insert(&Todo::new("Todo".to_string(), None, None, Priority::Low))
+            .unwrap();
+
+        assert!(db.size_on_disk().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_snapshot_restore_roundtrip() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = TodoDb::new(source_dir.path()).unwrap();
+        for title in ["One", "Two", "Three"] {
+            source
+                .insert(&Todo::new(title.to_string(), None, None, Priority::High))
+                .unwrap();
+        }
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let snapshot = snapshot_dir.path().join("todos.snapshot");
+        source.snapshot(&snapshot).unwrap();
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let target = TodoDb::new(target_dir.path()).unwrap();
+        target
+            .insert(&Todo::new(
+                "Replaced".to_string(),
+                None,
+                None,
+                Priority::Low,
+            ))
+            .unwrap();
+
+        assert_eq!(target.restore_from_snapshot(&snapshot).unwrap(), 3);
+        assert_eq!(target.count().unwrap(), source.count().unwrap());
+        assert!(!target.contains_title("Replaced").unwrap());
+        // Indexes come back with the data
+        assert_eq!(target.iter_by_priority_descending().count(), 3);
+    }
+
+    #[test]
+    fn test_ping_reads_schema_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let trees = db.db.tree_names();
+
+        db.ping().unwrap();
+        assert_eq!(db.count().unwrap(), 0);
+        assert_eq!(db.db.tree_names(), trees);
+
+        let meta = db.db.open_tree(crate::migration::META_TREE).unwrap();
+        meta.remove(crate::migration::SCHEMA_VERSION_KEY).unwrap();
+        assert!(db.ping().is_err());
+    }
+
+    #[test]
+    fn test_restore_rejects_malformed_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(dir.path().join("db")).unwrap();
+        let todo = Todo::new("Kept".to_string(), None, None, Priority::Low);
+        db.insert(&todo).unwrap();
+
+        let snapshot = dir.path().join("bad.snapshot");
+        let malformed: [Vec<SnapshotCollection>; 2] = [
+            vec![(b"bogus".to_vec(), b"todos".to_vec(), Vec::new())],
+            vec![(
+                b"tree".to_vec(),
+                b"__sled__default".to_vec(),
+                vec![vec![b"key only".to_vec()]],
+            )],
+        ];
+        for collections in malformed {
+            let bytes = bincode::encode_to_vec(&collections, bincode::config::standard()).unwrap();
+            std::fs::write(&snapshot, bytes).unwrap();
+
+            assert!(db.restore_from_snapshot(&snapshot).is_err());
+            assert_eq!(db.get(&todo.id).unwrap().unwrap().title, "Kept");
+        }
+    }
+
+    #[test]
+    fn test_notification_preferences_replace_per_user() {
+        use crate::NotificationChannel;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut preference = NotificationPreference {
+            user: "ada".to_string(),
+            channel: NotificationChannel::Email("ada@example.com".to_string()),
+            advance_hours: 24,
+        };
+        db.set_notification_preference(&preference).unwrap();
+        preference.channel = NotificationChannel::Webhook("https://example.com/hook".to_string());
+        db.set_notification_preference(&preference).unwrap();
+
+        assert_eq!(db.notification_preferences().unwrap(), vec![preference]);
+        // Preferences live in their own tree and are not todos
+        assert_eq!(db.count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_get_created_between() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2025, 1, d, 12, 0, 0).unwrap();
+
+        for (title, created) in [
+            ("Before", day(1)),
+            ("First", day(10)),
+            ("Last", day(19)),
+            ("After", day(20)),
+        ] {
+            let todo = Todo::new_with_timestamps(
+                title.to_string(),
+                None,
+                None,
+                Priority::Low,
+                created,
+                created,
+            );
+            db.insert(&todo).unwrap();
+        }
+
+        let todos = db.get_created_between(day(5), day(20)).unwrap();
+        let titles: Vec<&str> = todos.iter().map(|todo| todo.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Last"]);
+    }
+
+    #[test]
+    fn test_get_due_between() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let now = Utc::now();
+
+        let soon = Todo::new(
+            "Soon".to_string(),
+            None,
+            Some(now + chrono::Duration::hours(1)),
+            Priority::Low,
+        );
+        let later = Todo::new(
+            "Later".to_string(),
+            None,
+            Some(now + chrono::Duration::days(2)),
+            Priority::Low,
+        );
+        let mut done = soon.clone();
+        done.id = Uuid::new_v4();
+        done.mark_completed();
+        for todo in [&soon, &later, &done] {
+            db.insert(todo).unwrap();
+        }
+
+        let due = db
+            .get_due_between(now, now + chrono::Duration::days(1))
+            .unwrap();
+        let titles: Vec<&str> = due.iter().map(|todo| todo.title.as_str()).collect();
+        assert_eq!(titles, vec!["Soon"]);
+    }
+
+    #[test]
+    fn test_due_date_views() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let today = Utc::now().date_naive();
+        let end_of_today = today.and_hms_opt(23, 59, 59).unwrap().and_utc();
+        let next_week = end_of_today + chrono::Duration::weeks(1);
+
+        let overdue = Todo::new(
+            "Overdue".to_string(),
+            None,
+            Some(Utc::now() - chrono::Duration::weeks(2)),
+            Priority::High,
+        );
+        let tonight = Todo::new(
+            "Tonight".to_string(),
+            None,
+            Some(end_of_today),
+            Priority::Low,
+        );
+        let later = Todo::new("Later".to_string(), None, Some(next_week), Priority::Low);
+        let mut done = overdue.clone();
+        done.id = Uuid::new_v4();
+        done.mark_completed();
+        for todo in [&overdue, &tonight, &later, &done] {
+            db.insert(todo).unwrap();
+        }
+
+        let titles = |todos: Vec<Todo>| -> Vec<String> {
+            todos.into_iter().map(|todo| todo.title).collect()
+        };
+        assert_eq!(titles(db.get_overdue().unwrap()), ["Overdue"]);
+        assert_eq!(titles(db.get_due_today().unwrap()), ["Tonight"]);
+        assert_eq!(titles(db.get_due_this_week().unwrap()), ["Tonight"]);
+    }
+
+    #[test]
+    fn test_hooks_run_after_writes() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let inserts = Arc::new(AtomicUsize::new(0));
+        let updates = Arc::new(AtomicUsize::new(0));
+        let deletes = Arc::new(AtomicUsize::new(0));
+        let db = {
+            let (inserts, updates, deletes) = (inserts.clone(), updates.clone(), deletes.clone());
+            TodoDb::builder(temp_dir.path())
+                .on_insert(move |_| {
+                    inserts.fetch_add(1, Ordering::SeqCst);
+                })
+                .on_update(move |_| {
+                    updates.fetch_add(1, Ordering::SeqCst);
+                })
+                .on_delete(move |todo| {
+                    assert_eq!(todo.title, "Hooked");
+                    deletes.fetch_add(1, Ordering::SeqCst);
+                })
+                .open()
+                .unwrap()
+        };
+
+        let mut todo = Todo::new("Hooked".to_string(), None, None, Priority::Low);
+        db.insert(&todo).unwrap();
+        todo.mark_completed();
+        db.update(&todo).unwrap();
+        db.delete(&todo.id).unwrap();
+        // Deleting a missing todo must not fire the hook again
+        db.delete(&todo.id).unwrap();
+
+        assert_eq!(inserts.load(Ordering::SeqCst), 1);
+        assert_eq!(updates.load(Ordering::SeqCst), 1);
+        assert_eq!(deletes.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_explicit_cache_capacity() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::builder(temp_dir.path())
+            .cache_capacity_bytes(1024 * 1024)
+            .open()
+            .unwrap();
+
+        let todo = Todo::new("Cached".to_string(), None, None, Priority::Medium);
+        db.insert(&todo).unwrap();
+        assert_eq!(db.get(&todo.id).unwrap().unwrap().title, "Cached");
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut todo = Todo::new("With metadata".to_string(), None, None, Priority::Low);
+        todo.metadata = serde_json::json!({ "source": "import", "tags": ["a", "b"] });
+        db.insert(&todo).unwrap();
+
+        let retrieved = db.get(&todo.id).unwrap().unwrap();
+        assert_eq!(retrieved.metadata, todo.metadata);
+    }
+
+    #[test]
+    fn test_update_metadata_merges() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut todo = Todo::new("Merge".to_string(), None, None, Priority::Low);
+        todo.metadata = serde_json::json!({ "a": 1, "nested": { "x": true, "y": false } });
+        db.insert(&todo).unwrap();
+
+        let merged = db
+            .update_metadata(
+                &todo.id,
+                serde_json::json!({ "a": null, "b": 2, "nested": { "y": null } }),
+                1024,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            merged,
+            serde_json::json!({ "b": 2, "nested": { "x": true } })
+        );
+        let stored = db.get(&todo.id).unwrap().unwrap();
+        assert_eq!(stored.metadata, merged);
+        assert_eq!(stored.version, 2);
+
+        let err = db
+            .update_metadata(&todo.id, serde_json::json!({ "c": "x".repeat(1024) }), 1024)
+            .unwrap_err();
+        assert!(err.downcast_ref::<MetadataTooLarge>().is_some());
+        assert_eq!(db.get(&todo.id).unwrap().unwrap().version, 2);
+        assert!(
+            db.update_metadata(&Uuid::new_v4(), serde_json::json!({}), 1024)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_concurrent_metadata_patches_keep_every_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let todo = Todo::new("Shared".to_string(), None, None, Priority::Low);
+        db.insert(&todo).unwrap();
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let db = &db;
+                scope.spawn(move || {
+                    let patch = serde_json::json!({ format!("key{}", i): i });
+                    db.update_metadata(&todo.id, patch, 1024).unwrap();
+                });
+            }
+        });
+
+        let stored = db.get(&todo.id).unwrap().unwrap();
+        assert_eq!(stored.metadata.as_object().unwrap().len(), 8);
+        assert_eq!(stored.version, 9);
     }
 }
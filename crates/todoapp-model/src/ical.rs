@@ -0,0 +1,143 @@
+//! iCalendar (RFC 5545) export of todos with due dates.
+
+use crate::{Todo, TodoDb};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::io::Write;
+
+/// Longest content line allowed before folding, in octets
+const MAX_LINE_OCTETS: usize = 75;
+
+impl TodoDb {
+    /// Streams every todo with a due date into `writer` as a `VEVENT` of one
+    /// `VCALENDAR`, oldest first. Returns the number of events written.
+    pub fn export_to_ics_writer<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        write_line(writer, "BEGIN:VCALENDAR")?;
+        write_line(writer, "VERSION:2.0")?;
+        write_line(writer, "PRODID:-//todoapp//todoapp//EN")?;
+        let mut count = 0;
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if let Some(due) = todo.due_date {
+                write_event(writer, &todo, due)?;
+                count += 1;
+            }
+        }
+        write_line(writer, "END:VCALENDAR")?;
+        Ok(count)
+    }
+}
+
+fn write_event<W: Write>(writer: &mut W, todo: &Todo, due: DateTime<Utc>) -> Result<()> {
+    write_line(writer, "BEGIN:VEVENT")?;
+    write_line(writer, &format!("UID:{}@todoapp", todo.id))?;
+    write_line(writer, &format!("DTSTAMP:{}", format_time(todo.updated_at)))?;
+    write_line(writer, &format!("DTSTART:{}", format_time(due)))?;
+    write_line(writer, &format!("SUMMARY:{}", escape_text(&todo.title)))?;
+    if let Some(description) = &todo.description {
+        write_line(writer, &format!("DESCRIPTION:{}", escape_text(description)))?;
+    }
+    let status = if todo.completed {
+        "COMPLETED"
+    } else {
+        "NEEDS-ACTION"
+    };
+    write_line(writer, &format!("STATUS:{}", status))?;
+    write_line(writer, "END:VEVENT")
+}
+
+/// UTC date-time in the basic format, e.g. `20250115T120000Z`
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes the characters TEXT values reserve
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes `line` with CRLF endings, folding it so no physical line exceeds
+/// [`MAX_LINE_OCTETS`]; continuation lines start with a space
+fn write_line<W: Write>(writer: &mut W, line: &str) -> Result<()> {
+    let mut rest = line;
+    let mut limit = MAX_LINE_OCTETS;
+    loop {
+        let mut end = rest.len().min(limit);
+        // Never split a multi-byte character
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        writer
+            .write_all(chunk.as_bytes())
+            .and_then(|()| writer.write_all(b"\r\n"))
+            .context("Failed to write export")?;
+        if tail.is_empty() {
+            return Ok(());
+        }
+        writer.write_all(b" ").context("Failed to write export")?;
+        rest = tail;
+        // The leading space counts towards the limit
+        limit = MAX_LINE_OCTETS - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_export_to_ics_writer() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let due = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+
+        let mut dated = Todo::new(
+            "Pay rent, finally".to_string(),
+            Some("Bank; then landlord".to_string()),
+            Some(due),
+            Priority::High,
+        );
+        dated.mark_completed();
+        db.insert(&dated).unwrap();
+        db.insert(&Todo::new("Undated".to_string(), None, None, Priority::Low))
+            .unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(db.export_to_ics_writer(&mut out).unwrap(), 1);
+        let ics = String::from_utf8(out).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("DTSTART:20250115T120000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Pay rent\\, finally\r\n"));
+        assert!(ics.contains("DESCRIPTION:Bank\\; then landlord\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+        assert!(!ics.contains("Undated"));
+    }
+
+    #[test]
+    fn test_long_lines_are_folded() {
+        let mut out = Vec::new();
+        write_line(&mut out, &format!("SUMMARY:{}", "é".repeat(100))).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.split_terminator("\r\n").collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        let unfolded: String = lines.iter().map(|line| line.trim_start()).collect();
+        assert_eq!(unfolded, format!("SUMMARY:{}", "é".repeat(100)));
+    }
+}
@@ -1,12 +1,54 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Ordered by urgency, so `Critical > VeryHigh > High > Medium > Low`.
+///
+/// bincode stores the variant index, so new levels must only ever be
+/// appended; records written before a level existed then decode unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
     Low,
     Medium,
     High,
+    VeryHigh,
+    Critical,
+}
+
+impl Priority {
+    /// Every level, lowest first
+    pub const ALL: [Priority; 5] = [
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+        Priority::VeryHigh,
+        Priority::Critical,
+    ];
+
+    /// `0` for `Low` up to `4` for `Critical`; also the first byte of
+    /// priority index keys, so it must not change
+    pub fn to_numeric(&self) -> u8 {
+        match self {
+            Priority::Low => 0,
+            Priority::Medium => 1,
+            Priority::High => 2,
+            Priority::VeryHigh => 3,
+            Priority::Critical => 4,
+        }
+    }
+
+    pub fn from_numeric(value: u8) -> Option<Priority> {
+        match value {
+            0 => Some(Priority::Low),
+            1 => Some(Priority::Medium),
+            2 => Some(Priority::High),
+            3 => Some(Priority::VeryHigh),
+            4 => Some(Priority::Critical),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,8 +59,57 @@ pub struct Todo {
     pub due_date: Option<DateTime<Utc>>,
     pub priority: Priority,
     pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Percentage done, 0 to 100
+    pub progress: u8,
+    pub pinned: bool,
+    pub estimate_hours: Option<f32>,
+    pub started_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Starts at 1 and goes up by one with every stored update, for
+    /// optimistic concurrency; see [`TodoDb::update`]
+    pub version: u64,
+    /// When each editable field last changed, for field-level sync
+    pub field_updated_at: FieldTimestamps,
+    /// Labels such as `urgent`, trimmed and without duplicates
+    pub tags: Vec<String>,
+    /// Free-form JSON attached by callers, stored as a JSON string inside the bincode blob
+    #[serde(with = "metadata_serde")]
+    pub metadata: Value,
+}
+
+/// Last change time of each editable field of a [`Todo`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FieldTimestamps {
+    pub title: DateTime<Utc>,
+    pub description: DateTime<Utc>,
+    pub due_date: DateTime<Utc>,
+    pub priority: DateTime<Utc>,
+    pub completed: DateTime<Utc>,
+    pub progress: DateTime<Utc>,
+    pub pinned: DateTime<Utc>,
+    pub estimate_hours: DateTime<Utc>,
+    pub metadata: DateTime<Utc>,
+    pub tags: DateTime<Utc>,
+}
+
+impl FieldTimestamps {
+    /// Every field last changed at `at`
+    pub fn all(at: DateTime<Utc>) -> Self {
+        Self {
+            title: at,
+            description: at,
+            due_date: at,
+            priority: at,
+            completed: at,
+            progress: at,
+            pinned: at,
+            estimate_hours: at,
+            metadata: at,
+            tags: at,
+        }
+    }
 }
 
 impl Todo {
@@ -29,26 +120,171 @@ impl Todo {
         priority: Priority,
     ) -> Self {
         let now = Utc::now();
+        Self::new_with_timestamps(title, description, due_date, priority, now, now)
+    }
+
+    /// Like [`Todo::new`], but keeps the creation and change times of a todo
+    /// migrated from another system
+    pub fn new_with_timestamps(
+        title: String,
+        description: Option<String>,
+        due_date: Option<DateTime<Utc>>,
+        priority: Priority,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        // v7 ids embed the creation time, so sled keeps todos in creation order
+        let timestamp = uuid::Timestamp::from_unix(
+            uuid::NoContext,
+            created_at.timestamp().max(0) as u64,
+            created_at.timestamp_subsec_nanos(),
+        );
         Self {
-            id: Uuid::new_v4(),
+            id: Uuid::new_v7(timestamp),
             title,
             description,
             due_date,
             priority,
             completed: false,
-            created_at: now,
-            updated_at: now,
+            completed_at: None,
+            progress: 0,
+            pinned: false,
+            estimate_hours: None,
+            started_at: None,
+            created_at,
+            updated_at,
+            version: 1,
+            field_updated_at: FieldTimestamps::all(updated_at),
+            tags: Vec::new(),
+            metadata: empty_metadata(),
+        }
+    }
+
+    /// A copy of this todo under a new id, created now and not yet worked on.
+    /// Progress and start time describe work on the original, so they are
+    /// reset along with completion.
+    pub fn clone_with_new_id(&self) -> Todo {
+        let fresh = Todo::new(
+            self.title.clone(),
+            self.description.clone(),
+            self.due_date,
+            self.priority.clone(),
+        );
+        Todo {
+            pinned: self.pinned,
+            estimate_hours: self.estimate_hours,
+            tags: self.tags.clone(),
+            metadata: self.metadata.clone(),
+            ..fresh
         }
     }
 
     pub fn mark_completed(&mut self) {
+        let now = Utc::now();
+        if !self.completed {
+            self.completed_at = Some(now);
+        }
         self.completed = true;
-        self.updated_at = Utc::now();
+        self.field_updated_at.completed = now;
+        self.updated_at = now;
     }
 
     pub fn mark_incomplete(&mut self) {
+        let now = Utc::now();
         self.completed = false;
-        self.updated_at = Utc::now();
+        self.completed_at = None;
+        self.field_updated_at.completed = now;
+        self.updated_at = now;
+    }
+
+    /// Records when work began; starting an already started todo keeps the first time
+    pub fn start(&mut self) {
+        let now = Utc::now();
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+        self.updated_at = now;
+    }
+
+    /// Records partial completion; reaching 100% completes the todo
+    pub fn set_progress(&mut self, pct: u8) -> Result<(), &'static str> {
+        if pct > 100 {
+            return Err("progress must be between 0 and 100");
+        }
+        let now = Utc::now();
+        self.progress = pct;
+        self.field_updated_at.progress = now;
+        if pct == 100 {
+            self.mark_completed();
+        } else {
+            self.updated_at = now;
+        }
+        Ok(())
+    }
+
+    pub fn set_pinned(&mut self, pinned: bool) {
+        let now = Utc::now();
+        self.pinned = pinned;
+        self.field_updated_at.pinned = now;
+        self.updated_at = now;
+    }
+
+    /// Replaces the tags, trimming each and dropping empty and repeated ones
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        let now = Utc::now();
+        self.tags = clean_tags(tags);
+        self.field_updated_at.tags = now;
+        self.updated_at = now;
+    }
+
+    /// Merges `patch` into the metadata as a JSON Merge Patch, see [`merge_patch`]
+    pub fn merge_metadata(&mut self, patch: Value) {
+        let now = Utc::now();
+        merge_patch(&mut self.metadata, patch);
+        self.field_updated_at.metadata = now;
+        self.updated_at = now;
+    }
+
+    /// Pushes the due date back by `duration`, starting from now if there is none
+    pub fn snooze(&mut self, duration: Duration) {
+        let now = Utc::now();
+        self.due_date = Some(self.due_date.unwrap_or(now) + duration);
+        self.field_updated_at.due_date = now;
+        self.updated_at = now;
+    }
+
+    /// Whether the todo is still open past its due date
+    pub fn is_overdue(&self) -> bool {
+        !self.completed && self.due_date.is_some_and(|due| due < Utc::now())
+    }
+
+    /// Calendar days (UTC) from today to the due date: 0 when due today,
+    /// negative once the day has passed
+    pub fn days_until_due(&self) -> Option<i64> {
+        let today = Utc::now().date_naive();
+        self.due_date
+            .map(|due| (due.date_naive() - today).num_days())
+    }
+
+    /// Whether every field of `a` and `b` matches, unlike `==` which only compares ids
+    pub fn fields_eq(a: &Todo, b: &Todo) -> bool {
+        a.id == b.id
+            && a.title == b.title
+            && a.description == b.description
+            && a.due_date == b.due_date
+            && a.priority == b.priority
+            && a.completed == b.completed
+            && a.completed_at == b.completed_at
+            && a.progress == b.progress
+            && a.pinned == b.pinned
+            && a.estimate_hours == b.estimate_hours
+            && a.started_at == b.started_at
+            && a.created_at == b.created_at
+            && a.updated_at == b.updated_at
+            && a.version == b.version
+            && a.field_updated_at == b.field_updated_at
+            && a.tags == b.tags
+            && a.metadata == b.metadata
     }
 
     pub fn update(
@@ -57,22 +293,314 @@ impl Todo {
         description: Option<Option<String>>,
         due_date: Option<Option<DateTime<Utc>>>,
         priority: Option<Priority>,
+        metadata: Option<Value>,
+        estimate_hours: Option<Option<f32>>,
     ) {
+        let now = Utc::now();
         if let Some(t) = title {
             self.title = t;
+            self.field_updated_at.title = now;
         }
         if let Some(d) = description {
             self.description = d;
+            self.field_updated_at.description = now;
         }
         if let Some(dd) = due_date {
             self.due_date = dd;
+            self.field_updated_at.due_date = now;
         }
         if let Some(p) = priority {
             self.priority = p;
+            self.field_updated_at.priority = now;
+        }
+        if let Some(m) = metadata {
+            self.metadata = m;
+            self.field_updated_at.metadata = now;
+        }
+        if let Some(e) = estimate_hours {
+            self.estimate_hours = e;
+            self.field_updated_at.estimate_hours = now;
         }
-        self.updated_at = Utc::now();
+        self.updated_at = now;
     }
 }
 
+/// Todos are the same entity when their ids match, whatever their other
+/// fields; use [`Todo::fields_eq`] to compare contents
+impl PartialEq for Todo {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+/// One-line summary such as `[x] Buy milk (High, due 2025-01-15) [work, urgent]`;
+/// the tag list is left out when there are no tags
+impl fmt::Display for Todo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.completed { 'x' } else { ' ' };
+        write!(f, "[{}] {} ({:?}", status, self.title, self.priority)?;
+        if let Some(due) = self.due_date {
+            write!(f, ", due {}", due.format("%Y-%m-%d"))?;
+        }
+        write!(f, ")")?;
+        if !self.tags.is_empty() {
+            write!(f, " [{}]", self.tags.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Trims each tag and drops empty and repeated ones, keeping the first
+/// occurrence's position
+pub(crate) fn clean_tags(tags: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !cleaned.iter().any(|seen| seen == tag) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    cleaned
+}
+
+/// The default metadata value: an empty JSON object
+pub fn empty_metadata() -> Value {
+    Value::Object(Default::default())
+}
+
+/// Applies a JSON Merge Patch (RFC 7396) to `target`: objects are merged
+/// recursively, `null` values remove keys and everything else replaces.
+pub fn merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = empty_metadata();
+            }
+            if let Value::Object(map) = target {
+                for (key, value) in patch {
+                    if value.is_null() {
+                        map.remove(&key);
+                    } else {
+                        merge_patch(map.entry(key).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+// bincode cannot decode self-describing values like `serde_json::Value`,
+// so the metadata is stored as its JSON text instead.
+mod metadata_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(value: &Value, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = serde_json::to_string(value).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        let json = String::deserialize(deserializer)?;
+        serde_json::from_str(&json).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "async-db")]
+mod async_db;
+#[cfg(feature = "bench")]
+mod bench;
+mod builder;
+#[cfg(feature = "transfer")]
+mod convert;
 mod db;
-pub use db::TodoDb;
+mod ical;
+mod mem;
+pub mod migration;
+mod notification;
+mod opml;
+mod query;
+mod sort;
+mod split;
+mod stats;
+mod storage;
+mod todo_txt;
+#[cfg(feature = "bench")]
+pub use bench::SledBenchHarness;
+pub use builder::{BuilderError, TodoBuilder};
+pub use db::{Hook, MetadataTooLarge, ReindexReport, TodoDb, TodoDbBuilder, VersionConflict};
+pub use mem::MemoryTodoDb;
+pub use notification::{NotificationChannel, NotificationPreference};
+pub use opml::parse_opml;
+pub use query::TodoQuery;
+pub use sort::{SortBy, SortDirection};
+pub use split::{ReadonlyTodoDb, WriteTodoDb};
+pub use stats::{Granularity, Summary, TimelineBucket};
+pub use storage::TodoStorage;
+pub use todo_txt::ParseError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_update_touches_only_changed_fields() {
+        let mut todo = Todo::new("Draft".to_string(), None, None, Priority::Low);
+        let before = Utc::now() - Duration::hours(1);
+        todo.field_updated_at = FieldTimestamps::all(before);
+
+        todo.update(
+            Some("Final".to_string()),
+            None,
+            None,
+            Some(Priority::High),
+            None,
+            None,
+        );
+
+        assert!(todo.field_updated_at.title > before);
+        assert!(todo.field_updated_at.priority > before);
+        assert_eq!(todo.field_updated_at.description, before);
+        assert_eq!(todo.field_updated_at.completed, before);
+        assert_eq!(todo.field_updated_at.title, todo.updated_at);
+    }
+
+    #[test]
+    fn test_clone_with_new_id() {
+        let mut todo = Todo::new("Water plants".to_string(), None, None, Priority::High);
+        todo.set_tags(vec!["home".to_string()]);
+        todo.set_pinned(true);
+        todo.mark_completed();
+
+        let copy = todo.clone_with_new_id();
+        assert_ne!(copy.id, todo.id);
+        assert_eq!(copy.title, todo.title);
+        assert_eq!(copy.priority, Priority::High);
+        assert_eq!(copy.tags, todo.tags);
+        assert!(copy.pinned);
+        assert!(!copy.completed);
+        assert_eq!(copy.completed_at, None);
+        assert_eq!(copy.version, 1);
+        assert!(copy.created_at >= todo.updated_at);
+    }
+
+    #[test]
+    fn test_display_summary() {
+        let due = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+        let mut todo = Todo::new("Buy milk".to_string(), None, Some(due), Priority::High);
+        assert_eq!(todo.to_string(), "[ ] Buy milk (High, due 2025-01-15)");
+
+        todo.mark_completed();
+        todo.set_tags(vec!["work".to_string(), "urgent".to_string()]);
+        assert_eq!(
+            todo.to_string(),
+            "[x] Buy milk (High, due 2025-01-15) [work, urgent]"
+        );
+
+        todo.due_date = None;
+        todo.set_tags(Vec::new());
+        assert_eq!(todo.to_string(), "[x] Buy milk (High)");
+    }
+
+    #[test]
+    fn test_new_with_timestamps_orders_ids_by_creation() {
+        let created = Utc.with_ymd_and_hms(2020, 3, 1, 9, 0, 0).unwrap();
+        let old = Todo::new_with_timestamps(
+            "Imported".to_string(),
+            None,
+            None,
+            Priority::Low,
+            created,
+            created,
+        );
+        let new = Todo::new("Fresh".to_string(), None, None, Priority::Low);
+
+        assert_eq!(old.created_at, created);
+        assert_eq!(old.field_updated_at, FieldTimestamps::all(created));
+        assert!(old.id < new.id);
+    }
+
+    #[test]
+    fn test_due_helpers() {
+        let mut todo = Todo::new(
+            "Report".to_string(),
+            None,
+            Some(Utc::now() - Duration::days(2)),
+            Priority::Medium,
+        );
+        assert!(todo.is_overdue());
+        assert_eq!(todo.days_until_due(), Some(-2));
+
+        todo.mark_completed();
+        assert!(!todo.is_overdue());
+
+        todo.due_date = Some(Utc::now() + Duration::days(3));
+        assert_eq!(todo.days_until_due(), Some(3));
+        todo.due_date = None;
+        assert_eq!(todo.days_until_due(), None);
+    }
+
+    #[test]
+    fn test_priority_order_matches_numeric() {
+        assert!(Priority::Critical > Priority::VeryHigh && Priority::VeryHigh > Priority::High);
+        assert!(Priority::High > Priority::Medium && Priority::Medium > Priority::Low);
+        for a in &Priority::ALL {
+            for b in &Priority::ALL {
+                assert_eq!(a.cmp(b), a.to_numeric().cmp(&b.to_numeric()));
+            }
+        }
+        for value in 0..=u8::MAX {
+            match Priority::from_numeric(value) {
+                Some(priority) => assert_eq!(priority.to_numeric(), value),
+                None => assert!(value > 4),
+            }
+        }
+    }
+
+    #[test]
+    fn test_priority_encoding_is_stable() {
+        let config = bincode::config::standard();
+        for priority in Priority::ALL {
+            // Variant indexes are what older records hold on disk
+            let bytes = bincode::serde::encode_to_vec(&priority, config).unwrap();
+            assert_eq!(bytes, vec![priority.to_numeric()]);
+            let (decoded, _): (Priority, _) =
+                bincode::serde::decode_from_slice(&bytes, config).unwrap();
+            assert_eq!(decoded, priority);
+
+            let json = serde_json::to_string(&priority).unwrap();
+            assert_eq!(json, format!("\"{:?}\"", priority));
+            assert_eq!(serde_json::from_str::<Priority>(&json).unwrap(), priority);
+        }
+    }
+
+    #[test]
+    fn test_set_tags_cleans_up() {
+        let mut todo = Todo::new("Tagged".to_string(), None, None, Priority::Low);
+        todo.set_tags(vec![
+            " urgent ".to_string(),
+            "home".to_string(),
+            "".to_string(),
+            "urgent".to_string(),
+        ]);
+        assert_eq!(todo.tags, vec!["urgent", "home"]);
+        assert_eq!(todo.field_updated_at.tags, todo.updated_at);
+    }
+
+    #[test]
+    fn test_equality_is_by_id() {
+        let todo = Todo::new("Original".to_string(), None, None, Priority::Low);
+        let mut edited = todo.clone();
+        edited.update(Some("Edited".to_string()), None, None, None, None, None);
+
+        assert_eq!(todo, edited);
+        assert!(!Todo::fields_eq(&todo, &edited));
+        assert!(Todo::fields_eq(&todo, &todo.clone()));
+        assert_ne!(
+            todo,
+            Todo::new("Original".to_string(), None, None, Priority::Low)
+        );
+    }
+}
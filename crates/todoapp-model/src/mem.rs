@@ -0,0 +1,128 @@
+//! An in-memory [`TodoStorage`] backend.
+
+use crate::db::listing_order;
+use crate::{Todo, TodoStorage};
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// Keeps todos in a `HashMap` behind a mutex; clones share the same map.
+///
+/// Prefer it in tests of code that only needs [`TodoStorage`]: it needs no
+/// temporary directory and leaves nothing on disk. Use the sled-backed
+/// [`TodoDb`](crate::TodoDb) for anything that must survive a restart or
+/// relies on its indexes, change feeds, statistics or snapshots, and in tests
+/// of those features.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTodoDb {
+    todos: Arc<Mutex<HashMap<Uuid, Todo>>>,
+}
+
+impl MemoryTodoDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<Uuid, Todo>>> {
+        self.todos
+            .lock()
+            .map_err(|_| anyhow!("In-memory store poisoned by a panicked writer"))
+    }
+}
+
+impl TodoStorage for MemoryTodoDb {
+    fn insert(&self, todo: &Todo) -> Result<()> {
+        self.lock()?.insert(todo.id, todo.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &Uuid) -> Result<Option<Todo>> {
+        Ok(self.lock()?.get(id).cloned())
+    }
+
+    fn exists(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.lock()?.contains_key(id))
+    }
+
+    /// Pinned todos first, then newest first, like [`TodoDb::get_all`](crate::TodoDb::get_all)
+    fn get_all(&self) -> Result<Vec<Todo>> {
+        let mut todos: Vec<Todo> = self.lock()?.values().cloned().collect();
+        todos.sort_by_key(listing_order);
+        Ok(todos)
+    }
+
+    fn update(&self, todo: &Todo) -> Result<Todo> {
+        let mut todos = self.lock()?;
+        let mut stored = todo.clone();
+        if let Some(previous) = todos.get(&todo.id) {
+            stored.version = previous.version + 1;
+        }
+        todos.insert(todo.id, stored.clone());
+        Ok(stored)
+    }
+
+    fn delete(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.lock()?.remove(id).is_some())
+    }
+
+    fn clear_all(&self) -> Result<()> {
+        self.lock()?.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+
+    #[test]
+    fn test_todo_crud() {
+        let db = MemoryTodoDb::new();
+
+        // Create
+        let todo = Todo::new(
+            "Test todo".to_string(),
+            Some("Description".to_string()),
+            None,
+            Priority::High,
+        );
+        let id = todo.id;
+        db.insert(&todo).unwrap();
+
+        // Read
+        let retrieved = db.get(&id).unwrap().unwrap();
+        assert_eq!(retrieved, todo);
+        assert!(Todo::fields_eq(&retrieved, &todo));
+        assert_eq!(retrieved.title, "Test todo");
+        assert_eq!(retrieved.priority, Priority::High);
+
+        // Update
+        let mut updated_todo = retrieved.clone();
+        updated_todo.mark_completed();
+        db.update(&updated_todo).unwrap();
+
+        let retrieved_again = db.get(&id).unwrap().unwrap();
+        assert_eq!(retrieved_again, todo);
+        assert!(retrieved_again.completed);
+
+        // Delete
+        let deleted = db.delete(&id).unwrap();
+        assert!(deleted);
+        assert!(db.get(&id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_clones_share_data() {
+        let db = MemoryTodoDb::new();
+        let clone = db.clone();
+
+        let todo = Todo::new("Shared".to_string(), None, None, Priority::Low);
+        db.insert(&todo).unwrap();
+        assert_eq!(clone.get(&todo.id).unwrap().unwrap().title, "Shared");
+
+        clone.delete(&todo.id).unwrap();
+        assert!(db.get(&todo.id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,472 @@
+//! Versioned upgrades of the records stored in sled.
+//!
+//! Records are bincode blobs without field names, so every change to
+//! [`Todo`](crate::Todo) needs a new version and a step that re-encodes older
+//! records. Each step works on its own frozen copies of the layouts, so later
+//! changes to the live types can't alter what an old step reads or writes.
+
+use crate::db::transaction_error;
+use crate::{Priority, empty_metadata};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sled::transaction::Transactional;
+use sled::{Batch, Db};
+use uuid::Uuid;
+
+/// Schema version written by this build
+pub const CURRENT_VERSION: u8 = 6;
+
+pub(crate) const META_TREE: &str = "meta";
+pub(crate) const SCHEMA_VERSION_KEY: &str = "__schema_version";
+
+/// Upgrades a single record from `from_version` to [`CURRENT_VERSION`]
+pub fn migrate(raw: &[u8], from_version: u8) -> Result<Vec<u8>> {
+    let mut raw = raw.to_vec();
+    for version in from_version..CURRENT_VERSION {
+        raw = match version {
+            1 => v1_to_v2(&raw)?,
+            2 => v2_to_v3(&raw)?,
+            3 => v3_to_v4(&raw)?,
+            4 => v4_to_v5(&raw)?,
+            5 => v5_to_v6(&raw)?,
+            _ => bail!("No migration from schema version {}", version),
+        };
+    }
+    Ok(raw)
+}
+
+/// Brings every record in `db` up to [`CURRENT_VERSION`] and records the version.
+/// Databases without a version are empty (new) or predate versioning (v1).
+pub(crate) fn upgrade(db: &Db) -> Result<()> {
+    let meta = db
+        .open_tree(META_TREE)
+        .context("Failed to open meta tree")?;
+    let version = match meta
+        .get(SCHEMA_VERSION_KEY)
+        .context("Failed to read schema version")?
+    {
+        Some(bytes) => *bytes.first().context("Empty schema version")?,
+        None if db.is_empty() => CURRENT_VERSION,
+        None => 1,
+    };
+
+    if version > CURRENT_VERSION {
+        bail!(
+            "Database schema version {} is newer than supported version {}",
+            version,
+            CURRENT_VERSION
+        );
+    }
+    if version < CURRENT_VERSION {
+        // Every record is migrated before anything is written, then the
+        // records and the version are stored in one transaction, so a bad
+        // record or a crash leaves the old data rather than a mix of versions
+        let mut records = Batch::default();
+        for item in db.iter() {
+            let (key, value) = item.context("Failed to iterate over todos")?;
+            let migrated = migrate(&value, version)
+                .with_context(|| format!("Failed to migrate record {:?}", key))?;
+            records.insert(key, migrated);
+        }
+        (&**db, &meta)
+            .transaction(|(todos, meta)| {
+                todos.apply_batch(&records)?;
+                meta.insert(SCHEMA_VERSION_KEY, vec![CURRENT_VERSION])?;
+                Ok(())
+            })
+            .map_err(|err| transaction_error(err, "Failed to write migrated todos"))?;
+    } else {
+        meta.insert(SCHEMA_VERSION_KEY, vec![CURRENT_VERSION])
+            .context("Failed to write schema version")?;
+    }
+    db.flush().context("Failed to flush database")?;
+    Ok(())
+}
+
+/// The original record layout
+#[derive(Serialize, Deserialize)]
+struct TodoV1 {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Priority,
+    completed: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// v2 added metadata, completion/start times, pinning and estimates
+#[derive(Serialize, Deserialize)]
+struct TodoV2 {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Priority,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    pinned: bool,
+    estimate_hours: Option<f32>,
+    started_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    #[serde(with = "crate::metadata_serde")]
+    metadata: Value,
+}
+
+fn v1_to_v2(raw: &[u8]) -> Result<Vec<u8>> {
+    let config = bincode::config::standard();
+    let (old, _): (TodoV1, _) =
+        bincode::serde::decode_from_slice(raw, config).context("Failed to decode v1 todo")?;
+    let todo = TodoV2 {
+        id: old.id,
+        title: old.title,
+        description: old.description,
+        due_date: old.due_date,
+        priority: old.priority,
+        completed: old.completed,
+        // The real completion time was never recorded
+        completed_at: old.completed.then_some(old.updated_at),
+        pinned: false,
+        estimate_hours: None,
+        started_at: None,
+        created_at: old.created_at,
+        updated_at: old.updated_at,
+        metadata: empty_metadata(),
+    };
+    bincode::serde::encode_to_vec(&todo, config).context("Failed to encode v2 todo")
+}
+
+/// v3 added progress tracking
+#[derive(Serialize, Deserialize)]
+struct TodoV3 {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Priority,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    progress: u8,
+    pinned: bool,
+    estimate_hours: Option<f32>,
+    started_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    #[serde(with = "crate::metadata_serde")]
+    metadata: Value,
+}
+
+fn v2_to_v3(raw: &[u8]) -> Result<Vec<u8>> {
+    let config = bincode::config::standard();
+    let (old, _): (TodoV2, _) =
+        bincode::serde::decode_from_slice(raw, config).context("Failed to decode v2 todo")?;
+    let todo = TodoV3 {
+        id: old.id,
+        title: old.title,
+        description: old.description,
+        due_date: old.due_date,
+        priority: old.priority,
+        completed: old.completed,
+        completed_at: old.completed_at,
+        progress: if old.completed { 100 } else { 0 },
+        pinned: old.pinned,
+        estimate_hours: old.estimate_hours,
+        started_at: old.started_at,
+        created_at: old.created_at,
+        updated_at: old.updated_at,
+        metadata: old.metadata,
+    };
+    bincode::serde::encode_to_vec(&todo, config).context("Failed to encode v3 todo")
+}
+
+/// Per-field change times as of v4, before tags
+#[derive(Serialize, Deserialize)]
+struct FieldTimestampsV4 {
+    title: DateTime<Utc>,
+    description: DateTime<Utc>,
+    due_date: DateTime<Utc>,
+    priority: DateTime<Utc>,
+    completed: DateTime<Utc>,
+    progress: DateTime<Utc>,
+    pinned: DateTime<Utc>,
+    estimate_hours: DateTime<Utc>,
+    metadata: DateTime<Utc>,
+}
+
+impl FieldTimestampsV4 {
+    fn all(at: DateTime<Utc>) -> Self {
+        Self {
+            title: at,
+            description: at,
+            due_date: at,
+            priority: at,
+            completed: at,
+            progress: at,
+            pinned: at,
+            estimate_hours: at,
+            metadata: at,
+        }
+    }
+}
+
+/// v4 added per-field change times
+#[derive(Serialize, Deserialize)]
+struct TodoV4 {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Priority,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    progress: u8,
+    pinned: bool,
+    estimate_hours: Option<f32>,
+    started_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    field_updated_at: FieldTimestampsV4,
+    #[serde(with = "crate::metadata_serde")]
+    metadata: Value,
+}
+
+fn v3_to_v4(raw: &[u8]) -> Result<Vec<u8>> {
+    let config = bincode::config::standard();
+    let (old, _): (TodoV3, _) =
+        bincode::serde::decode_from_slice(raw, config).context("Failed to decode v3 todo")?;
+    let todo = TodoV4 {
+        id: old.id,
+        title: old.title,
+        description: old.description,
+        due_date: old.due_date,
+        priority: old.priority,
+        completed: old.completed,
+        completed_at: old.completed_at,
+        progress: old.progress,
+        pinned: old.pinned,
+        estimate_hours: old.estimate_hours,
+        started_at: old.started_at,
+        created_at: old.created_at,
+        updated_at: old.updated_at,
+        // Which field changed last was never recorded
+        field_updated_at: FieldTimestampsV4::all(old.updated_at),
+        metadata: old.metadata,
+    };
+    bincode::serde::encode_to_vec(&todo, config).context("Failed to encode v4 todo")
+}
+
+/// Per-field change times as of v5, with tags
+#[derive(Serialize, Deserialize)]
+struct FieldTimestampsV5 {
+    title: DateTime<Utc>,
+    description: DateTime<Utc>,
+    due_date: DateTime<Utc>,
+    priority: DateTime<Utc>,
+    completed: DateTime<Utc>,
+    progress: DateTime<Utc>,
+    pinned: DateTime<Utc>,
+    estimate_hours: DateTime<Utc>,
+    metadata: DateTime<Utc>,
+    tags: DateTime<Utc>,
+}
+
+/// v5 added tags
+#[derive(Serialize, Deserialize)]
+struct TodoV5 {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Priority,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    progress: u8,
+    pinned: bool,
+    estimate_hours: Option<f32>,
+    started_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    field_updated_at: FieldTimestampsV5,
+    tags: Vec<String>,
+    #[serde(with = "crate::metadata_serde")]
+    metadata: Value,
+}
+
+fn v4_to_v5(raw: &[u8]) -> Result<Vec<u8>> {
+    let config = bincode::config::standard();
+    let (old, _): (TodoV4, _) =
+        bincode::serde::decode_from_slice(raw, config).context("Failed to decode v4 todo")?;
+    let field_updated_at = FieldTimestampsV5 {
+        title: old.field_updated_at.title,
+        description: old.field_updated_at.description,
+        due_date: old.field_updated_at.due_date,
+        priority: old.field_updated_at.priority,
+        completed: old.field_updated_at.completed,
+        progress: old.field_updated_at.progress,
+        pinned: old.field_updated_at.pinned,
+        estimate_hours: old.field_updated_at.estimate_hours,
+        metadata: old.field_updated_at.metadata,
+        tags: old.created_at,
+    };
+    let todo = TodoV5 {
+        id: old.id,
+        title: old.title,
+        description: old.description,
+        due_date: old.due_date,
+        priority: old.priority,
+        completed: old.completed,
+        completed_at: old.completed_at,
+        progress: old.progress,
+        pinned: old.pinned,
+        estimate_hours: old.estimate_hours,
+        started_at: old.started_at,
+        created_at: old.created_at,
+        updated_at: old.updated_at,
+        field_updated_at,
+        tags: Vec::new(),
+        metadata: old.metadata,
+    };
+    bincode::serde::encode_to_vec(&todo, config).context("Failed to encode v5 todo")
+}
+
+/// v6 added a version counter. This is the current layout of
+/// [`Todo`](crate::Todo); keep it as is when the next version changes that.
+#[derive(Serialize, Deserialize)]
+struct TodoV6 {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
+    priority: Priority,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    progress: u8,
+    pinned: bool,
+    estimate_hours: Option<f32>,
+    started_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    version: u64,
+    field_updated_at: FieldTimestampsV5,
+    tags: Vec<String>,
+    #[serde(with = "crate::metadata_serde")]
+    metadata: Value,
+}
+
+fn v5_to_v6(raw: &[u8]) -> Result<Vec<u8>> {
+    let config = bincode::config::standard();
+    let (old, _): (TodoV5, _) =
+        bincode::serde::decode_from_slice(raw, config).context("Failed to decode v5 todo")?;
+    let todo = TodoV6 {
+        id: old.id,
+        title: old.title,
+        description: old.description,
+        due_date: old.due_date,
+        priority: old.priority,
+        completed: old.completed,
+        completed_at: old.completed_at,
+        progress: old.progress,
+        pinned: old.pinned,
+        estimate_hours: old.estimate_hours,
+        started_at: old.started_at,
+        created_at: old.created_at,
+        updated_at: old.updated_at,
+        // Earlier changes were not counted
+        version: 1,
+        field_updated_at: old.field_updated_at,
+        tags: old.tags,
+        metadata: old.metadata,
+    };
+    bincode::serde::encode_to_vec(&todo, config).context("Failed to encode v6 todo")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldTimestamps, Todo, TodoDb};
+
+    #[test]
+    fn test_v1_record_is_upgraded_on_open() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let old = TodoV1 {
+            id: Uuid::new_v4(),
+            title: "Legacy".to_string(),
+            description: None,
+            due_date: None,
+            priority: Priority::High,
+            completed: true,
+            created_at: now,
+            updated_at: now,
+        };
+
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            let config = bincode::config::standard();
+            let value = bincode::serde::encode_to_vec(&old, config).unwrap();
+            db.insert(old.id.as_bytes(), value).unwrap();
+            db.flush().unwrap();
+        }
+
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let todo = db.get(&old.id).unwrap().unwrap();
+        assert_eq!(todo.title, "Legacy");
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.completed_at, Some(now));
+        assert!(!todo.pinned);
+        assert_eq!(todo.progress, 100);
+        assert_eq!(todo.field_updated_at, FieldTimestamps::all(now));
+        assert!(todo.tags.is_empty());
+        assert_eq!(todo.version, 1);
+        assert_eq!(todo.metadata, empty_metadata());
+    }
+
+    #[test]
+    fn test_current_layout_matches_todo() {
+        let mut todo = Todo::new("Current".to_string(), None, None, Priority::Low);
+        todo.set_tags(vec!["home".to_string()]);
+        let config = bincode::config::standard();
+        let encoded = bincode::serde::encode_to_vec(&todo, config).unwrap();
+        let (frozen, _): (TodoV6, _) = bincode::serde::decode_from_slice(&encoded, config).unwrap();
+        assert_eq!(
+            bincode::serde::encode_to_vec(&frozen, config).unwrap(),
+            encoded
+        );
+    }
+
+    #[test]
+    fn test_failed_upgrade_leaves_records_untouched() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let old = TodoV1 {
+            id: Uuid::new_v4(),
+            title: "Legacy".to_string(),
+            description: None,
+            due_date: None,
+            priority: Priority::Low,
+            completed: false,
+            created_at: now,
+            updated_at: now,
+        };
+        let config = bincode::config::standard();
+        let value = bincode::serde::encode_to_vec(&old, config).unwrap();
+
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            db.insert(old.id.as_bytes(), value.clone()).unwrap();
+            // Sorts after the valid record, so it fails once that one is migrated
+            db.insert([0xff; 16], &b"garbage"[..]).unwrap();
+            db.flush().unwrap();
+        }
+        assert!(TodoDb::new(temp_dir.path()).is_err());
+
+        let db = sled::open(temp_dir.path()).unwrap();
+        assert_eq!(db.get(old.id.as_bytes()).unwrap().unwrap(), value);
+        let meta = db.open_tree(META_TREE).unwrap();
+        assert!(meta.get(SCHEMA_VERSION_KEY).unwrap().is_none());
+    }
+}
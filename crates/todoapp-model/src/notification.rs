@@ -0,0 +1,18 @@
+//! Due-date reminder preferences.
+
+use serde::{Deserialize, Serialize};
+
+/// Where a reminder is delivered
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum NotificationChannel {
+    Email(String),
+    Webhook(String),
+}
+
+/// A user's wish to be reminded `advance_hours` before todos are due
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotificationPreference {
+    pub user: String,
+    pub channel: NotificationChannel,
+    pub advance_hours: u8,
+}
@@ -0,0 +1,201 @@
+//! OPML export and import for outliner tools.
+//!
+//! Each priority becomes a top-level `<outline>` and its todos are nested
+//! underneath, with the description in the conventional `_note` attribute.
+
+use crate::{Priority, Todo, TodoDb};
+use anyhow::{Context, Result, bail};
+use quick_xml::Reader;
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use std::io::Write;
+
+impl TodoDb {
+    /// Writes every todo into `writer` as an OPML 2.0 document grouped by
+    /// priority, highest first. Returns the number of todos written.
+    pub fn export_to_opml_writer<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        let todos = self.iter_all().collect::<Result<Vec<_>>>()?;
+        let mut xml = Writer::new_with_indent(writer, b' ', 2);
+        xml.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+            .context("Failed to write export")?;
+        xml.write_event(Event::Start(
+            BytesStart::new("opml").with_attributes([("version", "2.0")]),
+        ))
+        .context("Failed to write export")?;
+        xml.write_event(Event::Start(BytesStart::new("head")))
+            .context("Failed to write export")?;
+        xml.write_event(Event::Start(BytesStart::new("title")))
+            .context("Failed to write export")?;
+        xml.write_event(Event::Text(BytesText::new("Todos")))
+            .context("Failed to write export")?;
+        xml.write_event(Event::End(BytesEnd::new("title")))
+            .context("Failed to write export")?;
+        xml.write_event(Event::End(BytesEnd::new("head")))
+            .context("Failed to write export")?;
+        xml.write_event(Event::Start(BytesStart::new("body")))
+            .context("Failed to write export")?;
+
+        for priority in Priority::ALL.into_iter().rev() {
+            let group = format!("{:?}", priority);
+            xml.write_event(Event::Start(
+                BytesStart::new("outline").with_attributes([("text", group.as_str())]),
+            ))
+            .context("Failed to write export")?;
+            for todo in todos.iter().filter(|todo| todo.priority == priority) {
+                let mut outline = BytesStart::new("outline").with_attributes([
+                    ("text", todo.title.as_str()),
+                    ("completed", if todo.completed { "true" } else { "false" }),
+                ]);
+                if let Some(description) = &todo.description {
+                    outline.push_attribute(("_note", description.as_str()));
+                }
+                xml.write_event(Event::Empty(outline))
+                    .context("Failed to write export")?;
+            }
+            xml.write_event(Event::End(BytesEnd::new("outline")))
+                .context("Failed to write export")?;
+        }
+
+        xml.write_event(Event::End(BytesEnd::new("body")))
+            .context("Failed to write export")?;
+        xml.write_event(Event::End(BytesEnd::new("opml")))
+            .context("Failed to write export")?;
+        Ok(todos.len() as u64)
+    }
+}
+
+/// Parses the todos out of an OPML document. Outlines nested under a
+/// priority outline (`Critical`, `VeryHigh`, `High`, `Medium` or `Low`) get
+/// that priority; any other
+/// outline without children becomes a `Medium` todo.
+pub fn parse_opml(xml: &str) -> Result<Vec<Todo>> {
+    let mut reader = Reader::from_str(xml);
+    let mut todos = Vec::new();
+    // Priority of each open `<outline>`, if its text names one
+    let mut open: Vec<Option<Priority>> = Vec::new();
+    loop {
+        match reader.read_event().context("Invalid OPML")? {
+            Event::Start(element) if element.name().as_ref() == b"outline" => {
+                let outline = Outline::from_element(&element)?;
+                open.push(parse_priority(&outline.text));
+                // A non-priority outline with children is still a todo
+                if open.last() == Some(&None) {
+                    todos.push(outline.into_todo(inherited_priority(&open[..open.len() - 1])));
+                }
+            }
+            Event::Empty(element) if element.name().as_ref() == b"outline" => {
+                let outline = Outline::from_element(&element)?;
+                todos.push(outline.into_todo(inherited_priority(&open)));
+            }
+            Event::End(element) if element.name().as_ref() == b"outline" => {
+                open.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(todos)
+}
+
+/// The attributes of one `<outline>` element that map onto a todo
+struct Outline {
+    text: String,
+    completed: bool,
+    note: Option<String>,
+}
+
+impl Outline {
+    fn from_element(element: &BytesStart) -> Result<Self> {
+        let mut outline = Outline {
+            text: String::new(),
+            completed: false,
+            note: None,
+        };
+        for attribute in element.attributes() {
+            let attribute = attribute.context("Invalid OPML attribute")?;
+            let value = attribute
+                .unescape_value()
+                .context("Invalid OPML attribute value")?
+                .into_owned();
+            match attribute.key.as_ref() {
+                b"text" => outline.text = value,
+                b"completed" => outline.completed = value == "true",
+                b"_note" => outline.note = Some(value),
+                _ => {}
+            }
+        }
+        if outline.text.trim().is_empty() {
+            bail!("OPML outline without text");
+        }
+        Ok(outline)
+    }
+
+    fn into_todo(self, priority: Priority) -> Todo {
+        let mut todo = Todo::new(self.text, self.note, None, priority);
+        if self.completed {
+            todo.mark_completed();
+        }
+        todo
+    }
+}
+
+fn parse_priority(text: &str) -> Option<Priority> {
+    Priority::ALL
+        .into_iter()
+        .find(|priority| format!("{:?}", priority).eq_ignore_ascii_case(text.trim()))
+}
+
+/// Priority given by the innermost enclosing priority outline
+fn inherited_priority(open: &[Option<Priority>]) -> Priority {
+    open.iter()
+        .rev()
+        .find_map(Clone::clone)
+        .unwrap_or(Priority::Medium)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opml_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let mut done = Todo::new(
+            "Ship <release>".to_string(),
+            Some("Tag & publish".to_string()),
+            None,
+            Priority::High,
+        );
+        done.mark_completed();
+        db.insert(&done).unwrap();
+        db.insert(&Todo::new("Tidy up".to_string(), None, None, Priority::Low))
+            .unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(db.export_to_opml_writer(&mut out).unwrap(), 2);
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("<outline text=\"High\">"));
+
+        let todos = parse_opml(&xml).unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].title, "Ship <release>");
+        assert_eq!(todos[0].description.as_deref(), Some("Tag & publish"));
+        assert_eq!(todos[0].priority, Priority::High);
+        assert!(todos[0].completed);
+        assert_eq!(todos[1].title, "Tidy up");
+        assert_eq!(todos[1].priority, Priority::Low);
+        assert!(!todos[1].completed);
+    }
+
+    #[test]
+    fn test_parse_opml_without_priority_groups() {
+        let xml = r#"<opml version="2.0"><body><outline text="Loose"/></body></opml>"#;
+        let todos = parse_opml(xml).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].priority, Priority::Medium);
+
+        assert!(parse_opml(r#"<opml><body><outline text=""/></body></opml>"#).is_err());
+    }
+}
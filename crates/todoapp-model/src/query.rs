@@ -0,0 +1,309 @@
+//! Filtering and paging for todo listings.
+
+use crate::db::listing_order;
+use crate::{Priority, Todo, TodoDb};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Criteria a listed todo must meet; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct TodoQuery {
+    pub priority: Option<Priority>,
+    pub completed: Option<bool>,
+    /// Only todos due strictly before this time
+    pub due_before: Option<DateTime<Utc>>,
+    /// Only todos due strictly after this time
+    pub due_after: Option<DateTime<Utc>>,
+    /// Only todos created strictly before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only todos created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Case-insensitive substring of the title or description
+    pub text: Option<String>,
+    /// Only todos carrying this exact tag
+    pub tag: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl TodoQuery {
+    /// Whether `todo` passes every filter; paging is not considered
+    pub fn matches(&self, todo: &Todo) -> bool {
+        if self
+            .priority
+            .as_ref()
+            .is_some_and(|priority| *priority != todo.priority)
+        {
+            return false;
+        }
+        if self
+            .completed
+            .is_some_and(|completed| completed != todo.completed)
+        {
+            return false;
+        }
+        if let Some(before) = self.due_before {
+            if !todo.due_date.is_some_and(|due| due < before) {
+                return false;
+            }
+        }
+        if let Some(after) = self.due_after {
+            if !todo.due_date.is_some_and(|due| due > after) {
+                return false;
+            }
+        }
+        if self
+            .created_before
+            .is_some_and(|before| todo.created_at >= before)
+        {
+            return false;
+        }
+        if self
+            .created_after
+            .is_some_and(|after| todo.created_at < after)
+        {
+            return false;
+        }
+        if let Some(tag) = &self.tag {
+            if !todo.tags.contains(tag) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            let text = text.to_lowercase();
+            let in_title = todo.title.to_lowercase().contains(&text);
+            let in_description = todo
+                .description
+                .as_ref()
+                .is_some_and(|description| description.to_lowercase().contains(&text));
+            if !in_title && !in_description {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Keeps the matching todos of an already ordered list and applies
+    /// paging. Also returns the number of matches before paging.
+    pub fn apply(&self, todos: impl IntoIterator<Item = Todo>) -> (Vec<Todo>, u64) {
+        let mut page = Vec::new();
+        let mut total = 0;
+        for todo in todos {
+            if !self.matches(&todo) {
+                continue;
+            }
+            if (self.offset..self.end()).contains(&total) {
+                page.push(todo);
+            }
+            total += 1;
+        }
+        (page, total as u64)
+    }
+
+    /// Index one past the last match a page can include
+    fn end(&self) -> usize {
+        self.limit
+            .map_or(usize::MAX, |limit| self.offset.saturating_add(limit))
+    }
+}
+
+impl TodoDb {
+    /// The todos matching `query`, ordered like [`get_all`](Self::get_all)
+    pub fn query(&self, query: &TodoQuery) -> Result<Vec<Todo>> {
+        Ok(self.query_with_total(query)?.0)
+    }
+
+    /// Like [`query`](Self::query), also returning the number of matches
+    /// before paging. Records are filtered as they are decoded, and only the
+    /// matches that can still land on the page are kept.
+    pub fn query_with_total(&self, query: &TodoQuery) -> Result<(Vec<Todo>, u64)> {
+        // A max-heap on the listing order, so the todo furthest down the
+        // listing is dropped once more matches are kept than the page needs
+        let mut kept = BinaryHeap::new();
+        let mut total = 0;
+        for todo in self.iter_all() {
+            let todo = todo?;
+            if !query.matches(&todo) {
+                continue;
+            }
+            total += 1;
+            kept.push(Listed(todo));
+            if kept.len() > query.end() {
+                kept.pop();
+            }
+        }
+        let page = kept
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Listed(todo)| todo)
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok((page, total))
+    }
+
+    /// Number of todos matching `query`, ignoring its paging
+    pub fn count_matching(&self, query: &TodoQuery) -> Result<u64> {
+        let mut total = 0;
+        for todo in self.iter_all() {
+            if query.matches(&todo?) {
+                total += 1;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Todos whose title or description contains `query`, ignoring case,
+    /// ordered like [`get_all`](Self::get_all)
+    pub fn search(&self, query: &str) -> Result<Vec<Todo>> {
+        self.query(&TodoQuery {
+            text: Some(query.to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+/// A todo compared by its position in listings
+struct Listed(Todo);
+
+impl Ord for Listed {
+    fn cmp(&self, other: &Self) -> Ordering {
+        listing_order(&self.0).cmp(&listing_order(&other.0))
+    }
+}
+
+impl PartialOrd for Listed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Listed {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Listed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_query_filters_and_pages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let now = Utc::now();
+
+        let mut groceries = Todo::new(
+            "Groceries".to_string(),
+            Some("Milk and eggs".to_string()),
+            Some(now + Duration::days(1)),
+            Priority::High,
+        );
+        groceries.mark_completed();
+        let taxes = Todo::new(
+            "Taxes".to_string(),
+            None,
+            Some(now + Duration::days(30)),
+            Priority::High,
+        );
+        let call = Todo::new("Call mom".to_string(), None, None, Priority::Low);
+        for todo in [&groceries, &taxes, &call] {
+            db.insert(todo).unwrap();
+        }
+
+        let titles = |query: TodoQuery| -> Vec<String> {
+            db.query(&query)
+                .unwrap()
+                .into_iter()
+                .map(|todo| todo.title)
+                .collect()
+        };
+        assert_eq!(
+            titles(TodoQuery {
+                priority: Some(Priority::High),
+                completed: Some(false),
+                ..Default::default()
+            }),
+            vec!["Taxes"]
+        );
+        assert_eq!(
+            titles(TodoQuery {
+                due_before: Some(now + Duration::days(7)),
+                ..Default::default()
+            }),
+            vec!["Groceries"]
+        );
+        assert_eq!(
+            titles(TodoQuery {
+                text: Some("EGGS".to_string()),
+                ..Default::default()
+            }),
+            vec!["Groceries"]
+        );
+        assert!(
+            titles(TodoQuery {
+                created_after: Some(now + Duration::days(1)),
+                ..Default::default()
+            })
+            .is_empty()
+        );
+        assert_eq!(
+            titles(TodoQuery {
+                offset: 1,
+                limit: Some(1),
+                ..Default::default()
+            }),
+            db.get_all().unwrap()[1..2]
+                .iter()
+                .map(|todo| todo.title.clone())
+                .collect::<Vec<_>>()
+        );
+
+        let (page, total) = db
+            .query_with_total(&TodoQuery {
+                priority: Some(Priority::High),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(total, 2);
+        let (page, total) = TodoQuery {
+            priority: Some(Priority::High),
+            offset: 1,
+            ..Default::default()
+        }
+        .apply(db.get_all().unwrap());
+        assert_eq!(page.len(), 1);
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn test_search_matches_title_and_description() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+
+        let groceries = Todo::new(
+            "Groceries".to_string(),
+            Some("Milk and eggs".to_string()),
+            None,
+            Priority::Low,
+        );
+        let milkshake = Todo::new("Buy MILKSHAKE".to_string(), None, None, Priority::Low);
+        let taxes = Todo::new("Taxes".to_string(), None, None, Priority::High);
+        for todo in [&groceries, &milkshake, &taxes] {
+            db.insert(todo).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let found = db.search("milk").unwrap();
+        assert_eq!(found, vec![milkshake, groceries]);
+        assert!(db.search("dentist").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! Client-selected orderings for todo listings.
+
+use crate::{Todo, TodoDb};
+use anyhow::Result;
+use std::cmp::Ordering;
+
+/// Field todos are ordered by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    CreatedAt,
+    UpdatedAt,
+    DueDate,
+    /// `High` before `Medium` before `Low` when descending
+    Priority,
+    /// Case-insensitive
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortBy {
+    /// The direction people usually expect: newest, most urgent or
+    /// alphabetical first
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            SortBy::CreatedAt | SortBy::UpdatedAt | SortBy::Priority => SortDirection::Desc,
+            SortBy::DueDate | SortBy::Title => SortDirection::Asc,
+        }
+    }
+
+    /// Orders `a` and `b` in direction `dir`. Todos without a due date come
+    /// last either way, and ties fall back to creation order.
+    pub fn compare(self, a: &Todo, b: &Todo, dir: SortDirection) -> Ordering {
+        let ordering = match self {
+            SortBy::CreatedAt => a.created_at.cmp(&b.created_at),
+            SortBy::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+            SortBy::DueDate => match (a.due_date, b.due_date) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            SortBy::Priority => a.priority.cmp(&b.priority),
+            SortBy::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        }
+        // Ids are UUIDv7, so they break ties in creation order
+        .then_with(|| a.id.cmp(&b.id));
+        match dir {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    }
+}
+
+impl TodoDb {
+    /// Every todo ordered by `sort_by` in direction `dir`
+    pub fn get_all_sorted(&self, sort_by: SortBy, dir: SortDirection) -> Result<Vec<Todo>> {
+        let mut todos = self.iter_all().collect::<Result<Vec<_>>>()?;
+        todos.sort_by(|a, b| sort_by.compare(a, b, dir));
+        Ok(todos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_get_all_sorted() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let now = Utc::now();
+
+        let banana = Todo::new(
+            "banana".to_string(),
+            None,
+            Some(now + Duration::days(2)),
+            Priority::Low,
+        );
+        let apple = Todo::new("Apple".to_string(), None, None, Priority::High);
+        let cherry = Todo::new(
+            "Cherry".to_string(),
+            None,
+            Some(now + Duration::days(1)),
+            Priority::Medium,
+        );
+        for todo in [&banana, &apple, &cherry] {
+            db.insert(todo).unwrap();
+        }
+
+        let titles = |sort_by, dir| -> Vec<String> {
+            db.get_all_sorted(sort_by, dir)
+                .unwrap()
+                .into_iter()
+                .map(|todo| todo.title)
+                .collect()
+        };
+        assert_eq!(
+            titles(SortBy::Title, SortDirection::Asc),
+            ["Apple", "banana", "Cherry"]
+        );
+        assert_eq!(
+            titles(SortBy::Priority, SortDirection::Desc),
+            ["Apple", "Cherry", "banana"]
+        );
+        assert_eq!(
+            titles(SortBy::DueDate, SortDirection::Asc),
+            ["Cherry", "banana", "Apple"]
+        );
+        // Undated todos stay last when descending too
+        assert_eq!(
+            titles(SortBy::DueDate, SortDirection::Desc),
+            ["banana", "Cherry", "Apple"]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Read-only and write-capable views over a shared [`TodoDb`].
+
+use crate::{Todo, TodoDb};
+use anyhow::Result;
+use uuid::Uuid;
+
+/// A view of the store that can only read, for code that must not mutate it
+#[derive(Debug, Clone)]
+pub struct ReadonlyTodoDb {
+    db: TodoDb,
+}
+
+/// A view of the store that can write todos
+#[derive(Debug, Clone)]
+pub struct WriteTodoDb {
+    db: TodoDb,
+}
+
+impl TodoDb {
+    /// Splits the handle into a read-only and a write-capable view. Both share
+    /// the same sled instance, so writes are visible to the reader right away.
+    pub fn split(&self) -> (ReadonlyTodoDb, WriteTodoDb) {
+        (
+            ReadonlyTodoDb { db: self.clone() },
+            WriteTodoDb { db: self.clone() },
+        )
+    }
+}
+
+impl ReadonlyTodoDb {
+    pub fn get(&self, id: &Uuid) -> Result<Option<Todo>> {
+        self.db.get(id)
+    }
+
+    pub fn get_all(&self) -> Result<Vec<Todo>> {
+        self.db.get_all()
+    }
+
+    pub fn iter_all(&self) -> impl Iterator<Item = Result<Todo>> + '_ {
+        self.db.iter_all()
+    }
+
+    pub fn count(&self) -> Result<u64> {
+        self.db.count()
+    }
+}
+
+impl WriteTodoDb {
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn insert(&self, todo: &Todo) -> Result<()> {
+        self.db.insert(todo)
+    }
+
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn update(&self, todo: &Todo) -> Result<Todo> {
+        self.db.update(todo)
+    }
+
+    #[must_use = "check whether the database operation succeeded"]
+    pub fn delete(&self, id: &Uuid) -> Result<bool> {
+        self.db.delete(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+
+    #[test]
+    fn test_split_views_share_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let (reader, writer) = db.split();
+
+        let todo = Todo::new("Shared".to_string(), None, None, Priority::Low);
+        writer.insert(&todo).unwrap();
+
+        assert_eq!(reader.count().unwrap(), 1);
+        assert_eq!(reader.get(&todo.id).unwrap().unwrap().title, "Shared");
+        assert_eq!(reader.iter_all().count(), 1);
+
+        assert!(writer.delete(&todo.id).unwrap());
+        assert!(reader.get_all().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::Todo;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// Bucket size for time-series statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// Format of a bucket's label. Every format is zero-padded from the most
+    /// significant unit down, so labels sort chronologically.
+    fn label_format(self) -> &'static str {
+        match self {
+            Granularity::Hour => "%Y-%m-%dT%H:00",
+            Granularity::Day => "%Y-%m-%d",
+            Granularity::Week => "%G-W%V",
+            Granularity::Month => "%Y-%m",
+        }
+    }
+
+    fn label(self, at: DateTime<Utc>) -> String {
+        at.format(self.label_format()).to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineBucket {
+    pub period: String,
+    pub created: u64,
+    pub completed: u64,
+}
+
+/// Counts over all todos, taken together so they agree with each other
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    pub total: u64,
+    pub completed: u64,
+    pub overdue: u64,
+    /// Sum of the estimates of todos that are not completed yet
+    pub total_estimated_hours: f32,
+}
+
+impl Summary {
+    pub(crate) fn add(&mut self, todo: &Todo) {
+        self.total += 1;
+        if todo.completed {
+            self.completed += 1;
+        } else if let Some(hours) = todo.estimate_hours {
+            self.total_estimated_hours += hours;
+        }
+        if todo.is_overdue() {
+            self.overdue += 1;
+        }
+    }
+}
+
+/// Counts creations and completions within `[from, to)` per bucket in a single
+/// pass. Buckets without any activity are omitted.
+pub(crate) fn timeline<'a>(
+    todos: impl IntoIterator<Item = &'a Todo>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    granularity: Granularity,
+) -> Vec<TimelineBucket> {
+    let in_range = |at: DateTime<Utc>| from <= at && at < to;
+    let mut buckets: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+
+    for todo in todos {
+        if in_range(todo.created_at) {
+            buckets
+                .entry(granularity.label(todo.created_at))
+                .or_default()
+                .0 += 1;
+        }
+        if let Some(completed_at) = todo.completed_at.filter(|at| in_range(*at)) {
+            buckets
+                .entry(granularity.label(completed_at))
+                .or_default()
+                .1 += 1;
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(period, (created, completed))| TimelineBucket {
+            period,
+            created,
+            completed,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_timeline_buckets_by_day() {
+        let at = |day, hour| Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap();
+        let todo = |created, completed| {
+            let mut todo = Todo::new("Todo".to_string(), None, None, Priority::Low);
+            todo.created_at = created;
+            todo.completed_at = completed;
+            todo
+        };
+        let todos = vec![
+            todo(at(1, 9), Some(at(2, 10))),
+            todo(at(1, 17), None),
+            todo(at(2, 8), None),
+            // Outside the requested range
+            todo(at(5, 8), Some(at(5, 9))),
+        ];
+
+        let buckets = timeline(&todos, at(1, 0), at(4, 0), Granularity::Day);
+        assert_eq!(
+            buckets,
+            vec![
+                TimelineBucket {
+                    period: "2026-03-01".to_string(),
+                    created: 2,
+                    completed: 0,
+                },
+                TimelineBucket {
+                    period: "2026-03-02".to_string(),
+                    created: 1,
+                    completed: 1,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! The storage interface shared by todo backends.
+
+use crate::{Todo, TodoDb};
+use anyhow::Result;
+use uuid::Uuid;
+
+/// Basic todo persistence, so callers that only need CRUD can work with any
+/// backend. [`TodoDb`] is the sled-backed implementation; its indexes,
+/// change feeds and statistics stay inherent methods.
+pub trait TodoStorage {
+    fn insert(&self, todo: &Todo) -> Result<()>;
+
+    fn get(&self, id: &Uuid) -> Result<Option<Todo>>;
+
+    /// Whether a todo with `id` is stored; backends can skip decoding it
+    fn exists(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.get(id)?.is_some())
+    }
+
+    fn get_all(&self) -> Result<Vec<Todo>>;
+
+    /// Stores `todo` over its previous record and returns it as stored, with
+    /// the version one past the previous one
+    fn update(&self, todo: &Todo) -> Result<Todo>;
+
+    /// Returns whether a todo was removed
+    fn delete(&self, id: &Uuid) -> Result<bool>;
+
+    fn clear_all(&self) -> Result<()>;
+}
+
+impl TodoStorage for TodoDb {
+    fn insert(&self, todo: &Todo) -> Result<()> {
+        TodoDb::insert(self, todo)
+    }
+
+    fn get(&self, id: &Uuid) -> Result<Option<Todo>> {
+        TodoDb::get(self, id)
+    }
+
+    fn exists(&self, id: &Uuid) -> Result<bool> {
+        TodoDb::exists(self, id)
+    }
+
+    fn get_all(&self) -> Result<Vec<Todo>> {
+        TodoDb::get_all(self)
+    }
+
+    fn update(&self, todo: &Todo) -> Result<Todo> {
+        TodoDb::update(self, todo)
+    }
+
+    fn delete(&self, id: &Uuid) -> Result<bool> {
+        TodoDb::delete(self, id)
+    }
+
+    fn clear_all(&self) -> Result<()> {
+        TodoDb::clear_all(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryTodoDb, Priority};
+
+    /// Exercises a backend only through the trait
+    fn roundtrip(storage: &impl TodoStorage) {
+        let mut todo = Todo::new("Generic".to_string(), None, None, Priority::Low);
+        assert!(!storage.exists(&todo.id).unwrap());
+        storage.insert(&todo).unwrap();
+        assert!(storage.exists(&todo.id).unwrap());
+        assert_eq!(storage.get(&todo.id).unwrap(), Some(todo.clone()));
+
+        todo.mark_completed();
+        todo.set_pinned(true);
+        let stored = storage.update(&todo).unwrap();
+        // One stored write is one version, however many fields changed
+        assert_eq!(stored.version, 2);
+        assert!(Todo::fields_eq(
+            &storage.get(&todo.id).unwrap().unwrap(),
+            &stored
+        ));
+        assert!(stored.completed);
+        assert_eq!(storage.get_all().unwrap().len(), 1);
+
+        assert!(storage.delete(&todo.id).unwrap());
+        storage.insert(&todo).unwrap();
+        storage.clear_all().unwrap();
+        assert!(storage.get_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_todo_db_implements_storage() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        roundtrip(&TodoDb::new(temp_dir.path()).unwrap());
+    }
+
+    #[test]
+    fn test_memory_db_implements_storage() {
+        roundtrip(&MemoryTodoDb::new());
+    }
+
+    #[test]
+    fn test_memory_db_orders_like_todo_db() {
+        let db = MemoryTodoDb::new();
+        // Created first, so it would come last without the pin
+        let mut pinned = Todo::new("Pinned".to_string(), None, None, Priority::Low);
+        pinned.set_pinned(true);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let older = Todo::new("Older".to_string(), None, None, Priority::Low);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let newer = Todo::new("Newer".to_string(), None, None, Priority::Low);
+        for todo in [&older, &newer, &pinned] {
+            db.insert(todo).unwrap();
+        }
+
+        let titles: Vec<String> = db
+            .get_all()
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.title)
+            .collect();
+        assert_eq!(titles, vec!["Pinned", "Newer", "Older"]);
+    }
+}
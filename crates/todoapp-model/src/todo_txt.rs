@@ -0,0 +1,234 @@
+//! The todo.txt line format (<https://github.com/todotxt/todo.txt>).
+//!
+//! Priorities map to `(A)` for critical, `(B)` for very high, `(C)` for high,
+//! `(D)` for medium and `(E)` for low.
+//! Due dates use the common `due:YYYY-MM-DD` extension, and completed lines
+//! keep their priority as `pri:X` since the format drops `(X)` on completion.
+//! Tags are written and read as `+project` words.
+
+use crate::{Priority, Todo, TodoDb, clean_tags};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fmt;
+use std::io::Write;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A todo.txt line that could not be turned into a [`Todo`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid todo.txt line {:?}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Todo {
+    /// Formats the todo as one todo.txt line, e.g. `(A) 2025-01-10 Buy milk +errand due:2025-01-15`
+    pub fn to_todo_txt(&self) -> String {
+        let mut parts = Vec::new();
+        match self.completed_at.filter(|_| self.completed) {
+            Some(completed_at) => {
+                parts.push("x".to_string());
+                parts.push(completed_at.format(DATE_FORMAT).to_string());
+            }
+            None if self.completed => parts.push("x".to_string()),
+            None => parts.push(format!("({})", priority_letter(&self.priority))),
+        }
+        parts.push(self.created_at.format(DATE_FORMAT).to_string());
+        // Line breaks would split the todo into several
+        parts.push(self.title.split_whitespace().collect::<Vec<_>>().join(" "));
+        // A tag must stay a single word to be read back as one
+        for tag in &self.tags {
+            parts.push(format!(
+                "+{}",
+                tag.split_whitespace().collect::<Vec<_>>().join("-")
+            ));
+        }
+        if let Some(due) = self.due_date {
+            parts.push(format!("due:{}", due.format(DATE_FORMAT)));
+        }
+        if self.completed {
+            parts.push(format!("pri:{}", priority_letter(&self.priority)));
+        }
+        parts.join(" ")
+    }
+
+    /// Parses a line written by [`to_todo_txt`](Self::to_todo_txt) or another
+    /// todo.txt tool. Todos without a priority get `Medium`.
+    pub fn from_todo_txt(line: &str) -> Result<Todo, ParseError> {
+        let error = |reason| ParseError {
+            line: line.to_string(),
+            reason,
+        };
+        let mut tokens = line.split_whitespace().peekable();
+
+        let completed = tokens.next_if_eq(&"x").is_some();
+        let mut priority = None;
+        let mut completed_at = None;
+        if completed {
+            completed_at = tokens.next_if(|token| parse_date(token).is_some());
+        } else if let Some(token) = tokens.next_if(|token| parse_priority_token(token).is_some()) {
+            priority = parse_priority_token(token);
+        }
+        let created_at = tokens.next_if(|token| parse_date(token).is_some());
+
+        let mut title = Vec::new();
+        let mut tags = Vec::new();
+        let mut due_date = None;
+        for token in tokens {
+            if let Some(value) = token.strip_prefix("due:") {
+                due_date = Some(parse_date(value).ok_or_else(|| error("invalid due date"))?);
+            } else if let Some(value) = token.strip_prefix("pri:") {
+                priority = Some(
+                    value
+                        .chars()
+                        .next()
+                        .and_then(letter_priority)
+                        .ok_or_else(|| error("invalid priority"))?,
+                );
+            } else if let Some(tag) = token.strip_prefix('+').filter(|tag| !tag.is_empty()) {
+                tags.push(tag.to_string());
+            } else {
+                title.push(token);
+            }
+        }
+        if title.is_empty() {
+            return Err(error("missing title"));
+        }
+
+        let mut todo = Todo::new(
+            title.join(" "),
+            None,
+            due_date,
+            priority.unwrap_or(Priority::Medium),
+        );
+        todo.tags = clean_tags(tags);
+        if let Some(created_at) = created_at.and_then(parse_date) {
+            todo.created_at = created_at;
+        }
+        if completed {
+            todo.mark_completed();
+            if let Some(completed_at) = completed_at.and_then(parse_date) {
+                todo.completed_at = Some(completed_at);
+            }
+        }
+        Ok(todo)
+    }
+}
+
+impl TodoDb {
+    /// Streams every todo into `writer` as todo.txt lines, oldest first.
+    /// Returns the number of todos written.
+    pub fn export_to_todo_txt_writer<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        let mut count = 0;
+        for todo in self.iter_all() {
+            writeln!(writer, "{}", todo?.to_todo_txt()).context("Failed to write export")?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+fn priority_letter(priority: &Priority) -> char {
+    match priority {
+        Priority::Critical => 'A',
+        Priority::VeryHigh => 'B',
+        Priority::High => 'C',
+        Priority::Medium => 'D',
+        Priority::Low => 'E',
+    }
+}
+
+/// `A` is critical down to `D` medium; todo.txt allows down to `Z`, all of
+/// which are low here
+fn letter_priority(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::Critical),
+        'B' => Some(Priority::VeryHigh),
+        'C' => Some(Priority::High),
+        'D' => Some(Priority::Medium),
+        'E'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_priority_token(token: &str) -> Option<Priority> {
+    let mut chars = token.strip_prefix('(')?.strip_suffix(')')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) => letter_priority(letter),
+        _ => None,
+    }
+}
+
+/// A `YYYY-MM-DD` date as midnight UTC
+fn parse_date(token: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(token, DATE_FORMAT)
+        .ok()
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_todo_txt_roundtrip() {
+        let created = Utc.with_ymd_and_hms(2025, 1, 10, 0, 0, 0).unwrap();
+        let due = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        let mut todo = Todo::new("Buy milk".to_string(), None, Some(due), Priority::Critical);
+        todo.created_at = created;
+        todo.set_tags(vec!["errand".to_string()]);
+        assert_eq!(
+            todo.to_todo_txt(),
+            "(A) 2025-01-10 Buy milk +errand due:2025-01-15"
+        );
+
+        let parsed = Todo::from_todo_txt(&todo.to_todo_txt()).unwrap();
+        assert_eq!(parsed.title, "Buy milk");
+        assert_eq!(parsed.tags, vec!["errand"]);
+        assert_eq!(parsed.priority, Priority::Critical);
+        assert_eq!(parsed.due_date, Some(due));
+        assert_eq!(parsed.created_at, created);
+        assert!(!parsed.completed);
+
+        todo.mark_completed();
+        todo.completed_at = Some(due);
+        let line = todo.to_todo_txt();
+        assert_eq!(
+            line,
+            "x 2025-01-15 2025-01-10 Buy milk +errand due:2025-01-15 pri:A"
+        );
+        let parsed = Todo::from_todo_txt(&line).unwrap();
+        assert!(parsed.completed);
+        assert_eq!(parsed.completed_at, Some(due));
+        assert_eq!(parsed.priority, Priority::Critical);
+    }
+
+    #[test]
+    fn test_from_todo_txt_accepts_other_tools_lines() {
+        let todo = Todo::from_todo_txt("(F) Call mom +family @phone").unwrap();
+        assert_eq!(todo.title, "Call mom @phone");
+        assert_eq!(todo.tags, vec!["family"]);
+        assert_eq!(todo.priority, Priority::Low);
+
+        let todo = Todo::from_todo_txt("Water plants").unwrap();
+        assert_eq!(todo.priority, Priority::Medium);
+
+        assert_eq!(
+            Todo::from_todo_txt("(A) 2025-01-10").unwrap_err().reason,
+            "missing title"
+        );
+        assert_eq!(
+            Todo::from_todo_txt("Pay due:tomorrow").unwrap_err().reason,
+            "invalid due date"
+        );
+    }
+}
@@ -1,31 +1,97 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
+/// Mirrors the model's priority levels, lowest first
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Priority {
     Low,
     Medium,
     High,
+    VeryHigh,
+    Critical,
 }
 
 /// Request to create a new todo
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTodoRequest {
     pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub due_date: Option<DateTime<Utc>>,
     pub priority: Priority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate_hours: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Original creation time when migrating from another system; only
+    /// accepted from requests carrying an admin token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 /// Request to update an existing todo
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateTodoRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub due_date: Option<Option<DateTime<Utc>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub priority: Option<Priority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub completed: Option<bool>,
+    /// Percentage done, 0 to 100; 100 also completes the todo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate_hours: Option<Option<f32>>,
+    /// Replaces all tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// The version the client last saw; the update is refused with a
+    /// conflict if the todo has changed since
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<u64>,
+}
+
+/// Request to defer a todo's due date, either by a number of hours or to a fixed time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SnoozeRequest {
+    Hours { hours: i64 },
+    Until { until: DateTime<Utc> },
+}
+
+/// Request to pin or unpin a todo; without a value the pin is toggled
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PinRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+}
+
+/// Request to change only a todo's priority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityRequest {
+    pub priority: Priority,
+}
+
+/// Request to reschedule a todo; `null` clears the due date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueDateRequest {
+    // Serialized even when `None`, as the explicit `null` is what clears it.
+    // Required, so a body without the field is rejected rather than taken
+    // as a request to clear the date.
+    #[serde(deserialize_with = "Option::deserialize")]
+    pub due_date: Option<DateTime<Utc>>,
 }
 
 /// Response containing a todo
@@ -33,12 +99,382 @@ pub struct UpdateTodoRequest {
 pub struct TodoResponse {
     pub id: Uuid,
     pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub due_date: Option<DateTime<Utc>>,
     pub priority: Priority,
     pub completed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    pub progress: u8,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate_hours: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Goes up with every change; send it back as `expected_version`
+    #[serde(default)]
+    pub version: u64,
+    pub field_updated_at: FieldTimestamps,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub metadata: Value,
+    /// Computed by the server: still open past the due date
+    #[serde(default)]
+    pub is_overdue: bool,
+}
+
+/// The [`TodoResponse`] fields a client asked for with `?fields=id,title`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseFieldSelector {
+    fields: Vec<String>,
+}
+
+impl SparseFieldSelector {
+    /// Field names of [`TodoResponse`] that can be selected
+    pub const VALID_FIELDS: &[&str] = &[
+        "id",
+        "title",
+        "description",
+        "due_date",
+        "priority",
+        "completed",
+        "completed_at",
+        "progress",
+        "pinned",
+        "estimate_hours",
+        "started_at",
+        "created_at",
+        "updated_at",
+        "version",
+        "field_updated_at",
+        "tags",
+        "metadata",
+        "is_overdue",
+    ];
+
+    /// Parses a comma-separated list of field names; the error names the
+    /// unknown fields and lists the valid ones
+    pub fn parse(fields: &str) -> Result<Self, String> {
+        let fields: Vec<String> = fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(str::to_string)
+            .collect();
+        if fields.is_empty() {
+            return Err("No fields selected".to_string());
+        }
+        let unknown: Vec<&str> = fields
+            .iter()
+            .map(String::as_str)
+            .filter(|field| !Self::VALID_FIELDS.contains(field))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!(
+                "Unknown fields: {}. Valid fields: {}",
+                unknown.join(", "),
+                Self::VALID_FIELDS.join(", ")
+            ));
+        }
+        Ok(Self { fields })
+    }
+
+    /// `todo` as a JSON object holding only the selected fields; optional
+    /// fields that are unset stay absent, as in the full response
+    pub fn select(&self, todo: &TodoResponse) -> Value {
+        let Ok(Value::Object(mut all)) = serde_json::to_value(todo) else {
+            return Value::Object(Default::default());
+        };
+        let selected = self
+            .fields
+            .iter()
+            .filter_map(|field| all.remove_entry(field))
+            .collect();
+        Value::Object(selected)
+    }
+}
+
+/// When each editable field of a todo last changed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FieldTimestamps {
+    pub title: DateTime<Utc>,
+    pub description: DateTime<Utc>,
+    pub due_date: DateTime<Utc>,
+    pub priority: DateTime<Utc>,
+    pub completed: DateTime<Utc>,
+    pub progress: DateTime<Utc>,
+    pub pinned: DateTime<Utc>,
+    pub estimate_hours: DateTime<Utc>,
+    pub metadata: DateTime<Utc>,
+    pub tags: DateTime<Utc>,
+}
+
+/// Change notification sent over a todo's event stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TodoEvent {
+    Updated {
+        todo: TodoResponse,
+    },
+    Deleted {
+        id: Uuid,
+    },
+    /// Something in the store changed; `counter` increases with every write
+    #[serde(rename = "any_change")]
+    AnyChange {
+        counter: u64,
+    },
+}
+
+/// Aggregate numbers across all todos
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatsResponse {
+    pub total: u64,
+    /// Todos not completed yet
+    pub active: u64,
+    pub completed: u64,
+    /// Active todos whose due date has passed
+    pub overdue: u64,
+    pub total_estimated_hours: f32,
+}
+
+/// Result of rebuilding the database's secondary indexes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReindexResponse {
+    pub records_processed: u64,
+    pub indexes_rebuilt: Vec<String>,
+}
+
+/// A todo sharing tags with another one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RelatedTodoResponse {
+    pub todo: TodoResponse,
+    /// How many of the other todo's tags this one carries too
+    pub shared_tags: u64,
+}
+
+/// Storage used by the database
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageResponse {
+    pub size_bytes: u64,
+    /// `size_bytes` formatted for people, e.g. "1.0 MB"
+    pub size_human: String,
+    pub record_count: u64,
+}
+
+/// Liveness of the server and its database, for load balancers and monitoring
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthResponse {
+    /// `ok`, or `unavailable` when the database cannot be read
+    pub status: String,
+    pub db_ok: bool,
+    /// Version of the server build
+    pub version: String,
+    pub uptime_secs: u64,
+}
+
+/// How a todo listing is paged
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Pagination {
+    /// `offset` and `limit` over a plain array
+    #[default]
+    Offset,
+    /// `after` and `limit`, returning a [`PaginatedTodosResponse`]
+    Cursor,
+}
+
+/// File format produced by the export endpoint
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    /// iCalendar, with one event per todo that has a due date
+    Ics,
+    /// OPML outline grouped by priority
+    Opml,
+    /// One todo.txt line per todo
+    #[serde(rename = "todo_txt")]
+    TodoTxt,
+}
+
+/// Query parameters for the export endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Outcome of deleting several todos at once
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BulkDeleteResponse {
+    pub deleted: Vec<Uuid>,
+    /// Requested ids that did not exist
+    pub not_found: Vec<Uuid>,
+}
+
+/// Response of clearing all completed todos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCompletedResponse {
+    pub deleted: u64,
+}
+
+/// One page of a cursor-paginated todo listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaginatedTodosResponse {
+    pub items: Vec<TodoResponse>,
+    /// Pass as `after` to fetch the next page; absent on the last page
+    pub next_cursor: Option<Uuid>,
+    /// Number of todos matching the filters, across all pages
+    pub total: u64,
+}
+
+/// Query parameters for the search endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchQuery {
+    /// Case-insensitive text searched for in titles and descriptions
+    pub q: String,
+}
+
+/// Query parameters for the import endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportQuery {
+    /// Skip items whose title already exists (case-insensitive)
+    #[serde(default)]
+    pub skip_duplicates: bool,
+}
+
+/// Outcome of importing a batch of todos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub imported: Vec<TodoResponse>,
+    /// Titles that were not imported because they already exist
+    pub skipped: Vec<String>,
+}
+
+/// Where due-date reminders are delivered
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "target", rename_all = "lowercase")]
+pub enum NotificationChannel {
+    /// Email address
+    Email(String),
+    /// URL that receives a POST per reminder
+    Webhook(String),
+}
+
+/// A user's reminder settings, sent to and returned by the preferences endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotificationPreference {
+    pub user: String,
+    pub channel: NotificationChannel,
+    /// How long before the due date to remind
+    pub advance_hours: u8,
+}
+
+/// Bucket size for timeline statistics
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+/// Field a todo listing is ordered by
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    CreatedAt,
+    UpdatedAt,
+    DueDate,
+    Priority,
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Query parameters for listing todos; filters combine with AND.
+/// Setting `after` or `paginate=cursor` pages with a cursor and returns a
+/// [`PaginatedTodosResponse`] instead of a plain array.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListTodosQuery {
+    /// `due_date_asc` or `priority_desc`; newest first when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Takes precedence over `sort`; pinned todos still come first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<SortBy>,
+    /// Defaults to the natural direction of `sort_by`, e.g. newest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_dir: Option<SortDirection>,
+    /// Cursor: the id of the last todo of the previous page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Uuid>,
+    /// `cursor` to page with a cursor from the first page on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paginate: Option<Pagination>,
+    /// Comma-separated ids; only these todos are returned, and ids with no
+    /// todo are left out rather than returned as `null`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<String>,
+    /// Comma-separated field names; only these fields are returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_before: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_after: Option<DateTime<Utc>>,
+    /// Only todos created strictly before this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only todos created at or after this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Case-insensitive text searched for in titles and descriptions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Only todos carrying this exact tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+}
+
+/// Query parameters for the timeline statistics endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineQuery {
+    #[serde(default)]
+    pub granularity: Granularity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Number of todos created and completed within one period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineBucket {
+    pub period: String,
+    pub created: u64,
+    pub completed: u64,
 }
 
 /// Error response
@@ -54,3 +490,99 @@ impl ErrorResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_date_request_requires_field() {
+        assert!(serde_json::from_str::<DueDateRequest>("{}").is_err());
+        let cleared: DueDateRequest = serde_json::from_str(r#"{"due_date": null}"#).unwrap();
+        assert_eq!(cleared.due_date, None);
+        let set: DueDateRequest =
+            serde_json::from_str(r#"{"due_date": "2025-03-01T00:00:00Z"}"#).unwrap();
+        assert_eq!(
+            set.due_date.unwrap().to_rfc3339(),
+            "2025-03-01T00:00:00+00:00"
+        );
+    }
+
+    fn sample_todo() -> TodoResponse {
+        let now = Utc::now();
+        TodoResponse {
+            id: Uuid::new_v4(),
+            title: "Write docs".to_string(),
+            description: None,
+            due_date: None,
+            priority: Priority::Low,
+            completed: false,
+            completed_at: None,
+            progress: 0,
+            pinned: false,
+            estimate_hours: None,
+            started_at: None,
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            field_updated_at: FieldTimestamps {
+                title: now,
+                description: now,
+                due_date: now,
+                priority: now,
+                completed: now,
+                progress: now,
+                pinned: now,
+                estimate_hours: now,
+                metadata: now,
+                tags: now,
+            },
+            tags: Vec::new(),
+            is_overdue: false,
+            metadata: Value::Object(Default::default()),
+        }
+    }
+
+    #[test]
+    fn test_missing_optional_fields_roundtrip() {
+        let todo = sample_todo();
+
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(!json.contains("description"));
+
+        let decoded: TodoResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.description, None);
+        assert_eq!(decoded, todo);
+    }
+
+    #[test]
+    fn test_sparse_field_selector() {
+        let todo = sample_todo();
+
+        let selector = SparseFieldSelector::parse("id, title,description").unwrap();
+        let selected = selector.select(&todo);
+        let keys: Vec<&String> = selected.as_object().unwrap().keys().collect();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(selected["title"], "Write docs");
+        assert_eq!(selected["id"], todo.id.to_string());
+
+        let err = SparseFieldSelector::parse("id,notes").unwrap_err();
+        assert!(err.contains("notes"));
+        assert!(err.contains("priority"));
+        assert!(SparseFieldSelector::parse(" , ").is_err());
+    }
+
+    #[test]
+    fn test_priority_serde_names() {
+        for (priority, name) in [
+            (Priority::Low, "\"Low\""),
+            (Priority::Medium, "\"Medium\""),
+            (Priority::High, "\"High\""),
+            (Priority::VeryHigh, "\"VeryHigh\""),
+            (Priority::Critical, "\"Critical\""),
+        ] {
+            assert_eq!(serde_json::to_string(&priority).unwrap(), name);
+            assert_eq!(serde_json::from_str::<Priority>(name).unwrap(), priority);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+#!/usr/bin/env sh
+# Create a todo. Usage: create.sh [title]
+set -eu
+API_BASE="${API_BASE:-http://127.0.0.1:3000/api}"
+TITLE="${1:-Write the weekly report}"
+
+curl -sSf -X POST "$API_BASE/todos" \
+    -H 'Content-Type: application/json' \
+    -d "{\"title\": \"$TITLE\", \"description\": \"Created from examples/http\", \"due_date\": null, \"priority\": \"High\", \"metadata\": {\"source\": \"curl\"}}"
@@ -0,0 +1,7 @@
+#!/usr/bin/env sh
+# Delete a todo. Usage: delete.sh <id>
+set -eu
+API_BASE="${API_BASE:-http://127.0.0.1:3000/api}"
+ID="$1"
+
+curl -sSf -X DELETE "$API_BASE/todos/$ID"
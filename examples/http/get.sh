@@ -0,0 +1,7 @@
+#!/usr/bin/env sh
+# Fetch a single todo. Usage: get.sh <id>
+set -eu
+API_BASE="${API_BASE:-http://127.0.0.1:3000/api}"
+ID="$1"
+
+curl -sSf "$API_BASE/todos/$ID"
@@ -0,0 +1,6 @@
+#!/usr/bin/env sh
+# List all todos, newest first
+set -eu
+API_BASE="${API_BASE:-http://127.0.0.1:3000/api}"
+
+curl -sSf "$API_BASE/todos"
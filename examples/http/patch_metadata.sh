@@ -0,0 +1,9 @@
+#!/usr/bin/env sh
+# Merge keys into a todo's metadata; null removes a key. Usage: patch_metadata.sh <id>
+set -eu
+API_BASE="${API_BASE:-http://127.0.0.1:3000/api}"
+ID="$1"
+
+curl -sSf -X PATCH "$API_BASE/todos/$ID/metadata" \
+    -H 'Content-Type: application/json' \
+    -d '{"source": null, "estimate": "2h"}'
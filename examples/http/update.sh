@@ -0,0 +1,9 @@
+#!/usr/bin/env sh
+# Rename a todo and mark it completed. Usage: update.sh <id>
+set -eu
+API_BASE="${API_BASE:-http://127.0.0.1:3000/api}"
+ID="$1"
+
+curl -sSf -X PUT "$API_BASE/todos/$ID" \
+    -H 'Content-Type: application/json' \
+    -d '{"title": "Write the weekly report (done)", "completed": true}'
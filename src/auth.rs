@@ -0,0 +1,130 @@
+//! Admin tokens, which unlock what ordinary clients may not do.
+
+use crate::{AppError, AppState};
+use axum::extract::OptionalFromRequestParts;
+use axum::http::{header, request::Parts};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use ring::hmac;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// A request bearing an HS256 JWT signed with `TODOAPP_JWT_SECRET` whose
+/// claims include `"admin": true`.
+///
+/// Extract it as `Option<AdminClaim>`: requests without an `Authorization`
+/// header, or with a valid token lacking the claim, get `None`. A malformed,
+/// forged or expired token is rejected with 401 rather than quietly treated
+/// as anonymous.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminClaim;
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    #[serde(default)]
+    admin: bool,
+    /// Expiry in seconds since the epoch
+    exp: Option<i64>,
+}
+
+impl<S> OptionalFromRequestParts<AppState<S>> for AdminClaim
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<S>,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let token = value
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| AppError::Unauthorized("Expected a bearer token".to_string()))?;
+        let key = state.config.jwt_key.as_ref().ok_or_else(|| {
+            AppError::Unauthorized("Admin tokens are not enabled on this server".to_string())
+        })?;
+        let claims = verify(token, key)
+            .ok_or_else(|| AppError::Unauthorized("Invalid or expired token".to_string()))?;
+        Ok(claims.admin.then_some(AdminClaim))
+    }
+}
+
+/// Checks the algorithm, signature and expiry of `token`, returning its claims
+fn verify(token: &str, key: &hmac::Key) -> Option<Claims> {
+    let (signed, signature) = token.rsplit_once('.')?;
+    let (header, claims) = signed.split_once('.')?;
+    let header: JwtHeader = decode_part(header)?;
+    // Only accept the algorithm the key is for, so `none` can't slip through
+    if header.alg != "HS256" {
+        return None;
+    }
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    hmac::verify(key, signed.as_bytes(), &signature).ok()?;
+    let claims: Claims = decode_part(claims)?;
+    match claims.exp {
+        Some(exp) if exp <= Utc::now().timestamp() => None,
+        _ => Some(claims),
+    }
+}
+
+fn decode_part<T: DeserializeOwned>(part: &str) -> Option<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(part).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Signs `claims` as an HS256 JWT, for tests that need a token
+#[cfg(test)]
+pub(crate) fn sign(claims: &serde_json::Value, key: &hmac::Key) -> String {
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+    let signed = format!("{}.{}", header, claims);
+    let signature = URL_SAFE_NO_PAD.encode(hmac::sign(key, signed.as_bytes()));
+    format!("{}.{}", signed, signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(secret: &str) -> hmac::Key {
+        hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())
+    }
+
+    #[test]
+    fn test_verify_accepts_signed_token() {
+        let token = sign(&json!({ "admin": true }), &key("secret"));
+        assert!(verify(&token, &key("secret")).unwrap().admin);
+    }
+
+    #[test]
+    fn test_verify_rejects_other_key() {
+        let token = sign(&json!({ "admin": true }), &key("other"));
+        assert!(verify(&token, &key("secret")).is_none());
+    }
+
+    #[test]
+    fn test_verify_rejects_expired_token() {
+        let exp = Utc::now().timestamp() - 60;
+        let token = sign(&json!({ "admin": true, "exp": exp }), &key("secret"));
+        assert!(verify(&token, &key("secret")).is_none());
+    }
+
+    #[test]
+    fn test_verify_rejects_unsigned_token() {
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(br#"{"admin":true}"#);
+        let token = format!("{}.{}.", header, claims);
+        assert!(verify(&token, &key("secret")).is_none());
+    }
+}
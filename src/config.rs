@@ -0,0 +1,231 @@
+use anyhow::{Context, Result, anyhow, bail};
+use axum::http::HeaderValue;
+use ring::hmac;
+use std::env;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Runtime settings read from `TODOAPP_*` environment variables
+///
+/// Every variable carries the full `TODOAPP_` prefix, which keeps them apart
+/// from unrelated `TODO_*` variables a shell may export; the short names
+/// (`TODO_PORT`, `TODO_HOST`, ...) are not read.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Address the server listens on (`TODOAPP_HOST`, default `127.0.0.1`)
+    pub host: String,
+    /// Port the server listens on (`TODOAPP_PORT`, default `3000`)
+    pub port: u16,
+    /// Origin allowed to call the API from a browser (`TODOAPP_CORS_ORIGIN`,
+    /// default `http://localhost:8080`, where the frontend dev server runs)
+    pub cors_origin: HeaderValue,
+    /// Tracing filter directives (`RUST_LOG`, default `info`)
+    pub log_level: String,
+    /// Directory holding the sled database (`TODOAPP_DB_PATH`)
+    pub db_path: PathBuf,
+    /// Size of sled's page cache in megabytes (`TODOAPP_DB_CACHE_MB`)
+    pub db_cache_mb: u64,
+    /// Maximum time a request may take before failing with 504 (`TODOAPP_REQUEST_TIMEOUT_SECS`)
+    pub request_timeout_secs: u64,
+    /// Requests handled at once before new ones queue (`TODOAPP_MAX_CONCURRENCY`)
+    pub max_concurrency: usize,
+    /// Built frontend served for non-API paths (`TODOAPP_STATIC_DIR`)
+    pub static_dir: PathBuf,
+    /// Time in-flight requests get to finish after a shutdown signal
+    /// (`TODOAPP_SHUTDOWN_TIMEOUT_SECS`)
+    pub shutdown_timeout_secs: u64,
+    /// Key admin JWTs are signed with (`TODOAPP_JWT_SECRET`, HS256); unset,
+    /// no request is treated as an admin
+    pub jwt_key: Option<hmac::Key>,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| env::var(name))
+    }
+
+    /// Like [`from_env`](Self::from_env), reading variables through `var`
+    fn from_vars(var: impl Fn(&str) -> Result<String, env::VarError>) -> Result<Self> {
+        let config = Self {
+            host: parse_or(&var, "TODOAPP_HOST", "127.0.0.1".to_string())?,
+            port: parse_or(&var, "TODOAPP_PORT", 3000)?,
+            cors_origin: parse_or(
+                &var,
+                "TODOAPP_CORS_ORIGIN",
+                HeaderValue::from_static("http://localhost:8080"),
+            )?,
+            log_level: parse_or(&var, "RUST_LOG", "info".to_string())?,
+            db_path: parse_or(&var, "TODOAPP_DB_PATH", PathBuf::from("./data"))?,
+            db_cache_mb: parse_or(&var, "TODOAPP_DB_CACHE_MB", 64)?,
+            request_timeout_secs: parse_or(&var, "TODOAPP_REQUEST_TIMEOUT_SECS", 30)?,
+            // Zero permits would queue every request forever
+            max_concurrency: parse_or(
+                &var,
+                "TODOAPP_MAX_CONCURRENCY",
+                NonZeroUsize::new(256).unwrap(),
+            )?
+            .get(),
+            static_dir: parse_or(
+                &var,
+                "TODOAPP_STATIC_DIR",
+                PathBuf::from("crates/todoapp-frontend/dist"),
+            )?,
+            shutdown_timeout_secs: parse_or(&var, "TODOAPP_SHUTDOWN_TIMEOUT_SECS", 30)?,
+            jwt_key: match var("TODOAPP_JWT_SECRET") {
+                Ok(secret) if secret.is_empty() => bail!("TODOAPP_JWT_SECRET must not be empty"),
+                Ok(secret) => Some(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())),
+                Err(env::VarError::NotPresent) => None,
+                Err(err) => return Err(err).context("Failed to read TODOAPP_JWT_SECRET"),
+            },
+        };
+        config.db_cache_bytes()?;
+        Ok(config)
+    }
+
+    /// `host:port`, as passed to the listener
+    pub fn bind_addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// Creates the database directory and checks that it's writable, so a bad
+    /// `TODOAPP_DB_PATH` is reported plainly instead of as a sled error
+    pub fn check_db_path(&self) -> Result<()> {
+        let probe = self.db_path.join(".write_check");
+        fs::create_dir_all(&self.db_path)
+            .and_then(|()| fs::write(&probe, b""))
+            .and_then(|()| fs::remove_file(&probe))
+            .map_err(|err| {
+                anyhow!(
+                    "Cannot create or write to database directory '{}': {}. Check TODOAPP_DB_PATH.",
+                    self.db_path.display(),
+                    err
+                )
+            })
+    }
+
+    pub fn db_cache_bytes(&self) -> Result<u64> {
+        self.db_cache_mb
+            .checked_mul(1024 * 1024)
+            .ok_or_else(|| anyhow!("TODOAPP_DB_CACHE_MB is too large: {}", self.db_cache_mb))
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+}
+
+/// The documented defaults, ignoring the environment
+impl Default for Config {
+    fn default() -> Self {
+        Self::from_vars(|_| Err(env::VarError::NotPresent)).expect("default settings are valid")
+    }
+}
+
+/// Parses variable `name`, falling back to `default` when unset
+fn parse_or<T>(
+    var: &impl Fn(&str) -> Result<String, env::VarError>,
+    name: &str,
+    default: T,
+) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match var(name) {
+        Ok(value) => value
+            .parse()
+            .with_context(|| format!("Invalid value for {}: {:?}", name, value)),
+        Err(env::VarError::NotPresent) => Ok(default),
+        Err(err) => Err(err).with_context(|| format!("Failed to read {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config_from(vars: &[(&str, &str)]) -> Result<Config> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Config::from_vars(|name| vars.get(name).cloned().ok_or(env::VarError::NotPresent))
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = config_from(&[]).unwrap();
+        assert_eq!(config.bind_addr(), "127.0.0.1:3000");
+        assert_eq!(config.cors_origin, "http://localhost:8080");
+        assert_eq!(config.log_level, "info");
+        assert_eq!(config.db_path, PathBuf::from("./data"));
+        assert!(config.jwt_key.is_none());
+    }
+
+    #[test]
+    fn test_overflowing_cache_size_is_rejected() {
+        let too_large = u64::MAX.to_string();
+        let err = config_from(&[("TODOAPP_DB_CACHE_MB", too_large.as_str())]).unwrap_err();
+        assert!(err.to_string().contains("TODOAPP_DB_CACHE_MB"));
+        let config = config_from(&[("TODOAPP_DB_CACHE_MB", "2")]).unwrap();
+        assert_eq!(config.db_cache_bytes().unwrap(), 2 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_zero_concurrency_is_rejected() {
+        let err = config_from(&[("TODOAPP_MAX_CONCURRENCY", "0")]).unwrap_err();
+        assert!(err.to_string().contains("TODOAPP_MAX_CONCURRENCY"));
+        let config = config_from(&[("TODOAPP_MAX_CONCURRENCY", "1")]).unwrap();
+        assert_eq!(config.max_concurrency, 1);
+    }
+
+    #[test]
+    fn test_empty_jwt_secret_is_rejected() {
+        let err = config_from(&[("TODOAPP_JWT_SECRET", "")]).unwrap_err();
+        assert!(err.to_string().contains("TODOAPP_JWT_SECRET"));
+    }
+
+    #[test]
+    fn test_variables_override_defaults() {
+        let config = config_from(&[
+            ("TODOAPP_HOST", "0.0.0.0"),
+            ("TODOAPP_PORT", "8000"),
+            ("TODOAPP_CORS_ORIGIN", "https://todo.example.com"),
+            ("TODOAPP_DB_PATH", "/var/lib/todoapp"),
+            ("RUST_LOG", "debug,sled=warn"),
+        ])
+        .unwrap();
+        assert_eq!(config.bind_addr(), "0.0.0.0:8000");
+        assert_eq!(config.cors_origin, "https://todo.example.com");
+        assert_eq!(config.db_path, PathBuf::from("/var/lib/todoapp"));
+        assert_eq!(config.log_level, "debug,sled=warn");
+    }
+
+    #[test]
+    fn test_invalid_port_names_variable() {
+        let err = config_from(&[("TODOAPP_PORT", "70000")]).unwrap_err();
+        assert!(err.to_string().contains("TODOAPP_PORT"));
+    }
+
+    #[test]
+    fn test_short_prefix_is_ignored() {
+        let config = config_from(&[
+            ("TODO_HOST", "0.0.0.0"),
+            ("TODO_PORT", "8000"),
+            ("TODO_CORS_ORIGIN", "https://todo.example.com"),
+            ("TODO_DB_PATH", "/var/lib/todoapp"),
+        ])
+        .unwrap();
+        assert_eq!(config.bind_addr(), "127.0.0.1:3000");
+        assert_eq!(config.cors_origin, "http://localhost:8080");
+        assert_eq!(config.db_path, PathBuf::from("./data"));
+    }
+}
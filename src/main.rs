@@ -1,122 +1,872 @@
+mod auth;
+mod config;
+mod middleware;
+
+use anyhow::Context;
+use auth::AdminClaim;
 use axum::{
-    Json, Router,
-    extract::{Path, State},
-    http::{HeaderValue, StatusCode},
-    response::{IntoResponse, Response},
-    routing::{delete, get, post, put},
+    BoxError, Json, Router,
+    body::Body,
+    error_handling::HandleErrorLayer,
+    extract::{
+        FromRequest, OptionalFromRequest, Path, Query, Request, State, rejection::JsonRejection,
+    },
+    http::{StatusCode, header},
+    middleware::from_fn,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{delete, get, patch, post, put},
+};
+use config::Config;
+use futures_util::{Stream, StreamExt, future, stream};
+use middleware::{
+    assign_request_id, log_error_responses, log_requests, make_request_span,
+    redirect_trailing_slash,
 };
+use std::collections::HashSet;
+use std::future::IntoFuture;
+use std::io::{self, BufWriter, Write};
 use std::sync::Arc;
-use todoapp_model::{Priority as ModelPriority, Todo, TodoDb};
+use std::time::{Duration, Instant};
+use todoapp_model::{
+    Granularity as ModelGranularity, MetadataTooLarge,
+    NotificationChannel as ModelNotificationChannel,
+    NotificationPreference as ModelNotificationPreference, SortBy as ModelSortBy,
+    SortDirection as ModelSortDirection, Todo, TodoDb, TodoQuery, TodoStorage, VersionConflict,
+    parse_opml,
+};
 use todoapp_transfer::{
-    CreateTodoRequest, ErrorResponse, Priority, TodoResponse, UpdateTodoRequest,
+    BulkDeleteResponse, CreateTodoRequest, DeleteCompletedResponse, DueDateRequest, ErrorResponse,
+    ExportFormat, ExportQuery, Granularity, HealthResponse, ImportQuery, ImportResult,
+    ListTodosQuery, NotificationChannel, NotificationPreference, PaginatedTodosResponse,
+    Pagination, PinRequest, PriorityRequest, ReindexResponse, RelatedTodoResponse, SearchQuery,
+    SnoozeRequest, SortBy, SortDirection, SparseFieldSelector, StatsResponse, StorageResponse,
+    TimelineBucket, TimelineQuery, TodoEvent, TodoResponse, UpdateTodoRequest,
 };
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tower::{ServiceBuilder, limit::GlobalConcurrencyLimitLayer, timeout::TimeoutLayer};
 use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
-use tracing::{error, info};
+use tracing::{Span, error, info, warn};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 use uuid::Uuid;
 
-#[derive(Clone)]
-struct AppState {
-    db: Arc<TodoDb>,
+/// Maximum size of a todo's serialized metadata
+const MAX_METADATA_BYTES: usize = 4 * 1024;
+
+/// Response header carrying the number of todos matching the filters, before paging
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+/// `Cache-Control` for a single open todo
+const TODO_CACHE_CONTROL: &str = "public, max-age=5";
+
+/// Buffered chunks between the export task and the response body
+const EXPORT_CHANNEL_CHUNKS: usize = 16;
+
+/// Range covered by the timeline stats when no `from` is given
+const DEFAULT_TIMELINE_DAYS: i64 = 30;
+
+/// Page size of cursor-paginated listings without a `limit`
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Most todos returned by `/todos/{id}/related`
+const RELATED_TODOS_LIMIT: usize = 10;
+
+/// How often the reminder task looks for todos coming due
+const REMINDER_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Shared handler state. Handlers that only need CRUD are generic over the
+/// backend `S`; the rest use the sled-backed [`TodoDb`] for its indexes,
+/// change feeds and statistics.
+#[derive(Debug, Clone)]
+struct AppState<S = TodoDb> {
+    db: S,
+    /// Cancelled once the server should stop; long-running tasks select on it
+    shutdown_token: CancellationToken,
+    /// When the server started, for the uptime in health checks
+    started_at: Instant,
+    /// Settings the server was started with
+    config: Arc<Config>,
+}
+
+impl<S> AppState<S> {
+    fn new(db: S, config: Config) -> Self {
+        Self {
+            db,
+            shutdown_token: CancellationToken::new(),
+            started_at: Instant::now(),
+            config: Arc::new(config),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
+    let config = Config::from_env().expect("Invalid configuration");
+
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(EnvFilter::try_new(&config.log_level).unwrap_or_else(|_| EnvFilter::new("info")))
         .with(fmt::layer())
         .init();
 
     info!("Starting todoapp backend");
+    if let Err(err) = config.check_db_path() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    let cache_bytes = config.db_cache_bytes().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
 
     // Initialize database
-    let db = TodoDb::new("./data").expect("Failed to open database");
-    let state = AppState { db: Arc::new(db) };
+    let db = TodoDb::builder(&config.db_path)
+        .cache_capacity_bytes(cache_bytes)
+        .open()
+        .expect("Failed to open database");
+    let state = AppState::new(db.clone(), config.clone());
+    let shutdown_token = state.shutdown_token.clone();
+    info!(?state, "Opened database");
+
+    if !config.static_dir.is_dir() {
+        warn!(
+            static_dir = %config.static_dir.display(),
+            "Static directory not found, serving the API only"
+        );
+    }
+
+    let app = build_app(state);
+
+    // Start server
+    let bind_addr = config.bind_addr();
+    let listener = tokio::net::TcpListener::bind(&bind_addr)
+        .await
+        .unwrap_or_else(|err| panic!("Failed to bind to {}: {}", bind_addr, err));
+
+    info!("Server running on http://{}", bind_addr);
+
+    tokio::spawn(cancel_on_signal(shutdown_token.clone()));
+    tokio::spawn(send_due_reminders(db.clone(), shutdown_token.clone()));
+    serve(listener, app, shutdown_token, config.shutdown_timeout())
+        .await
+        .expect("Failed to start server");
 
+    // sled buffers writes, so flush before the process exits
+    match db.close() {
+        Ok(()) => info!("Database flushed, exiting"),
+        Err(err) => error!(error = %err, "failed to close database"),
+    }
+}
+
+/// Routes served by any [`TodoStorage`] backend
+fn storage_routes<S>() -> Router<AppState<S>>
+where
+    S: TodoStorage + Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/todos", post(create_todo::<S>))
+        .route("/todos/{id}", get(get_todo::<S>))
+        .route("/todos/{id}", delete(delete_todo::<S>))
+}
+
+fn build_app(state: AppState) -> Router {
+    let config = state.config.clone();
     // Build API router
-    let api_router = Router::new()
+    let api_router = storage_routes()
         .route("/todos", get(list_todos))
-        .route("/todos", post(create_todo))
-        .route("/todos/{id}", get(get_todo))
+        .route("/todos/bulk", post(create_todos_bulk))
+        .route("/todos/bulk", delete(delete_todos_bulk))
+        .route("/todos/completed", delete(delete_completed_todos))
+        .route("/todos/import", post(import_todos))
+        .route("/todos/import/opml", post(import_opml))
+        .route("/todos/pinned", get(list_pinned_todos))
+        .route("/todos/search", get(search_todos))
+        .route("/todos/overdue", get(list_overdue_todos))
+        .route("/todos/due-today", get(list_todos_due_today))
+        .route("/todos/due-this-week", get(list_todos_due_this_week))
+        .route("/todos/random", get(random_todo))
+        .route("/todos/events", get(all_todo_events))
+        .route("/todos/export", get(export_todos))
+        .route("/todos/stats", get(stats))
+        .route("/todos/stats/timeline", get(timeline_stats))
         .route("/todos/{id}", put(update_todo))
-        .route("/todos/{id}", delete(delete_todo))
-        .with_state(state);
+        .route("/todos/{id}/events", get(todo_events))
+        .route("/todos/{id}/metadata", get(get_todo_metadata))
+        .route("/todos/{id}/metadata", patch(patch_todo_metadata))
+        .route("/todos/{id}/snooze", post(snooze_todo))
+        .route("/todos/{id}/pin", post(pin_todo))
+        .route("/todos/{id}/duplicate", post(duplicate_todo))
+        .route("/todos/{id}/related", get(related_todos))
+        .route("/todos/{id}/priority", patch(set_todo_priority))
+        .route("/todos/{id}/due-date", patch(set_todo_due_date))
+        .route("/todos/{id}/start", post(start_todo))
+        .route("/todos/{id}/complete", post(complete_todo))
+        .route("/todos/{id}/incomplete", post(reopen_todo))
+        .route("/tags", get(list_tags))
+        .route("/health", get(health))
+        .route(
+            "/notifications/preferences",
+            post(set_notification_preference),
+        )
+        .route("/admin/reindex", post(reindex))
+        .route("/admin/storage", get(storage))
+        .with_state(state)
+        .layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(handle_middleware_error))
+                // Router::layer wraps every route separately, so the limit needs a
+                // shared semaphore to bound the API as a whole
+                .layer(GlobalConcurrencyLimitLayer::new(config.max_concurrency))
+                .layer(TimeoutLayer::new(config.request_timeout())),
+        )
+        // Outermost so the latency includes queueing and timeouts
+        .layer(from_fn(log_requests));
 
     // Build main router with CORS and static file serving
-    let app = Router::new()
+    Router::new()
         .nest("/api", api_router)
-        .fallback_service(ServeDir::new("crates/todoapp-frontend/dist"))
-        .layer(
-            CorsLayer::permissive()
-                .allow_origin("http://localhost:8080".parse::<HeaderValue>().unwrap()),
-        )
-        .layer(TraceLayer::new_for_http());
+        .fallback_service(ServeDir::new(&config.static_dir))
+        .layer(from_fn(redirect_trailing_slash))
+        .layer(CorsLayer::permissive().allow_origin(config.cors_origin.clone()))
+        .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+        .layer(from_fn(log_error_responses))
+        .layer(from_fn(assign_request_id))
+}
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-        .await
-        .expect("Failed to bind to port 3000");
+/// Serves `app` until `shutdown_token` is cancelled, then gives in-flight
+/// requests up to `drain_timeout` to finish before dropping them
+async fn serve(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    shutdown_token: CancellationToken,
+    drain_timeout: Duration,
+) -> std::io::Result<()> {
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_token.clone().cancelled_owned())
+        .into_future();
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => return result,
+        () = shutdown_token.cancelled() => {}
+    }
+    match tokio::time::timeout(drain_timeout, server).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!(
+                timeout_secs = drain_timeout.as_secs_f64(),
+                "In-flight requests did not finish in time, dropping them"
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn cancel_on_signal(shutdown_token: CancellationToken) {
+    if let Err(err) = shutdown_signal().await {
+        error!(error = %err, "failed to listen for shutdown signal");
+        return;
+    }
+    info!("Shutdown signal received, stopping server");
+    shutdown_token.cancel();
+}
+
+/// Resolves on SIGINT (Ctrl+C) or SIGTERM, which container runtimes send to
+/// stop a deployment
+#[cfg(unix)]
+async fn shutdown_signal() -> io::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
 
-    info!("Server running on http://127.0.0.1:3000");
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() -> io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
 
-    axum::serve(listener, app)
+/// Every [`REMINDER_INTERVAL`], reminds each user about the todos whose reminder
+/// time (due date minus `advance_hours`) fell within the last interval, so every
+/// todo is announced once per preference
+async fn send_due_reminders(db: TodoDb, shutdown_token: CancellationToken) {
+    let window = chrono::Duration::from_std(REMINDER_INTERVAL).unwrap();
+    let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+    // The first tick completes immediately; skip it so restarts don't resend
+    interval.tick().await;
+    while shutdown_token
+        .run_until_cancelled(interval.tick())
         .await
-        .expect("Failed to start server");
+        .is_some()
+    {
+        let now = chrono::Utc::now();
+        let preferences = match db.notification_preferences() {
+            Ok(preferences) => preferences,
+            Err(err) => {
+                error!(error = %err, "failed to load notification preferences");
+                continue;
+            }
+        };
+        for preference in preferences {
+            let advance = chrono::Duration::hours(preference.advance_hours.into());
+            match db.get_due_between(now - window + advance, now + advance) {
+                Ok(todos) => {
+                    for todo in todos {
+                        send_reminder(&preference, &todo);
+                    }
+                }
+                Err(err) => error!(error = %err, "failed to look up due todos"),
+            }
+        }
+    }
+}
+
+/// Delivers a single reminder; delivery is only logged for now
+fn send_reminder(preference: &ModelNotificationPreference, todo: &Todo) {
+    match &preference.channel {
+        ModelNotificationChannel::Email(address) => {
+            info!(
+                user = %preference.user,
+                %address,
+                todo.id = %todo.id,
+                "Sending reminder email: {}",
+                todo
+            )
+        }
+        ModelNotificationChannel::Webhook(url) => {
+            info!(
+                user = %preference.user,
+                %url,
+                todo.id = %todo.id,
+                "Calling reminder webhook: {}",
+                todo
+            )
+        }
+    }
 }
 
 // Handlers
 
-async fn list_todos(State(state): State<AppState>) -> Result<Json<Vec<TodoResponse>>, AppError> {
-    info!("Listing todos");
-    let todos = state.db.get_all()?;
-    let responses: Vec<TodoResponse> = todos.into_iter().map(todo_to_response).collect();
-    Ok(Json(responses))
+async fn list_todos(
+    State(state): State<AppState>,
+    Query(params): Query<ListTodosQuery>,
+) -> Result<Response, AppError> {
+    info!(
+        sort = ?params.sort,
+        sort_by = ?params.sort_by,
+        sort_dir = ?params.sort_dir,
+        after = ?params.after,
+        paginate = ?params.paginate,
+        ids = ?params.ids,
+        fields = ?params.fields,
+        "Listing todos"
+    );
+    let selector = params
+        .fields
+        .as_deref()
+        .map(SparseFieldSelector::parse)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    let query = TodoQuery {
+        priority: params.priority.clone().map(Into::into),
+        completed: params.completed,
+        due_before: params.due_before,
+        due_after: params.due_after,
+        created_before: params.created_before,
+        created_after: params.created_after,
+        text: params.q.clone(),
+        tag: params.tag.clone(),
+        offset: params.offset.unwrap_or(0),
+        limit: params.limit,
+    };
+    if params.after.is_some() || params.paginate == Some(Pagination::Cursor) {
+        if params.ids.is_some() || selector.is_some() || params.sort.is_some() {
+            return Err(AppError::BadRequest(
+                "Cursor pagination cannot be combined with ids, fields or sort; use sort_by"
+                    .to_string(),
+            ));
+        }
+        return Ok(Json(list_todos_page(&state, &params, &query)?).into_response());
+    }
+    let (todos, total) = if let Some(ids) = params.ids.as_deref() {
+        let ids = parse_ids(ids)?;
+        // Unknown ids are left out rather than failing the whole request
+        query.apply(
+            state
+                .db
+                .get_many(&ids)?
+                .into_iter()
+                .filter_map(|(_, todo)| todo),
+        )
+    } else if let Some(sort_by) = params.sort_by {
+        let sort_by = sort_by_to_model(sort_by);
+        let dir = params
+            .sort_dir
+            .map(sort_direction_to_model)
+            .unwrap_or_else(|| sort_by.default_direction());
+        let mut todos = state.db.get_all_sorted(sort_by, dir)?;
+        todos.sort_by_key(|todo| !todo.pinned);
+        query.apply(todos)
+    } else {
+        match params.sort.as_deref() {
+            None => state.db.query_with_total(&query)?,
+            Some("due_date_asc") => query.apply(state.db.get_all_by_due_date()?),
+            Some("priority_desc") => {
+                let mut todos = state
+                    .db
+                    .iter_by_priority_descending()
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                // Pinned todos stay on top whatever the sort order
+                todos.sort_by_key(|todo| !todo.pinned);
+                query.apply(todos)
+            }
+            Some(other) => {
+                return Err(AppError::BadRequest(format!(
+                    "Unsupported sort order: {}",
+                    other
+                )));
+            }
+        }
+    };
+
+    let responses: Vec<TodoResponse> = todos.into_iter().map(TodoResponse::from).collect();
+    let headers = [(TOTAL_COUNT_HEADER, total.to_string())];
+    Ok(match selector {
+        Some(selector) => {
+            let sparse: Vec<serde_json::Value> =
+                responses.iter().map(|todo| selector.select(todo)).collect();
+            (headers, Json(sparse)).into_response()
+        }
+        None => (headers, Json(responses)).into_response(),
+    })
+}
+
+fn list_todos_page(
+    state: &AppState,
+    params: &ListTodosQuery,
+    query: &TodoQuery,
+) -> Result<PaginatedTodosResponse, AppError> {
+    let sort_by = params
+        .sort_by
+        .map(sort_by_to_model)
+        .unwrap_or(ModelSortBy::CreatedAt);
+    let dir = params
+        .sort_dir
+        .map(sort_direction_to_model)
+        .unwrap_or_else(|| sort_by.default_direction());
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if let Some(after) = params.after {
+        // Only creation order can resume from a deleted todo
+        if sort_by != ModelSortBy::CreatedAt && !state.db.exists(&after)? {
+            return Err(AppError::BadRequest(format!(
+                "Cursor {} does not exist",
+                after
+            )));
+        }
+    }
+
+    let page = state
+        .db
+        .get_page(params.after.as_ref(), limit, sort_by, dir, query)?;
+    let next_cursor = if page.len() == limit {
+        page.last().map(|todo| todo.id)
+    } else {
+        None
+    };
+    Ok(PaginatedTodosResponse {
+        items: page.into_iter().map(TodoResponse::from).collect(),
+        next_cursor,
+        total: state.db.count_matching(query)?,
+    })
 }
 
-async fn create_todo(
+async fn list_pinned_todos(
     State(state): State<AppState>,
-    Json(req): Json<CreateTodoRequest>,
-) -> Result<(StatusCode, Json<TodoResponse>), AppError> {
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    info!("Listing pinned todos");
+    let todos = state.db.get_pinned()?;
+    Ok(Json(todos.into_iter().map(TodoResponse::from).collect()))
+}
+
+async fn list_tags(State(state): State<AppState>) -> Result<Json<Vec<String>>, AppError> {
+    info!("Listing tags");
+    Ok(Json(state.db.all_tags()?))
+}
+
+async fn search_todos(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    info!(q = %query.q, "Searching todos");
+    let text = query.q.trim();
+    if text.is_empty() {
+        return Err(AppError::BadRequest(
+            "Search text must not be empty".to_string(),
+        ));
+    }
+    let todos = state.db.search(text)?;
+    Ok(Json(todos.into_iter().map(TodoResponse::from).collect()))
+}
+
+async fn delete_completed_todos(
+    State(state): State<AppState>,
+) -> Result<Json<DeleteCompletedResponse>, AppError> {
+    info!("Deleting completed todos");
+    let deleted = state.db.delete_completed()?;
+    info!(deleted, "Deleted completed todos");
+    Ok(Json(DeleteCompletedResponse { deleted }))
+}
+
+async fn list_overdue_todos(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    info!("Listing overdue todos");
+    let todos = state.db.get_overdue()?;
+    Ok(Json(todos.into_iter().map(TodoResponse::from).collect()))
+}
+
+async fn list_todos_due_today(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    info!("Listing todos due today");
+    let todos = state.db.get_due_today()?;
+    Ok(Json(todos.into_iter().map(TodoResponse::from).collect()))
+}
+
+async fn list_todos_due_this_week(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TodoResponse>>, AppError> {
+    info!("Listing todos due this week");
+    let todos = state.db.get_due_this_week()?;
+    Ok(Json(todos.into_iter().map(TodoResponse::from).collect()))
+}
+
+async fn create_todo<S>(
+    State(state): State<AppState<S>>,
+    admin: Option<AdminClaim>,
+    JsonBody(req): JsonBody<CreateTodoRequest>,
+) -> Result<(StatusCode, Json<TodoResponse>), AppError>
+where
+    S: TodoStorage + Clone + Send + Sync + 'static,
+{
     info!(title = %req.title, "Creating todo");
-    let todo = Todo::new(
-        req.title,
-        req.description,
-        req.due_date,
-        priority_to_model(req.priority),
+    reject_backdating(&req, admin)?;
+    let todo = todo_from_request(req)?;
+    let stored = todo.clone();
+    run_storage(&state.db, move |db| db.insert(&stored)).await?;
+    Ok((StatusCode::CREATED, Json(todo.into())))
+}
+
+async fn create_todos_bulk(
+    State(state): State<AppState>,
+    admin: Option<AdminClaim>,
+    JsonBody(reqs): JsonBody<Vec<CreateTodoRequest>>,
+) -> Result<(StatusCode, Json<Vec<TodoResponse>>), AppError> {
+    info!(count = reqs.len(), "Creating todos in bulk");
+    let todos = reqs
+        .into_iter()
+        .map(|req| {
+            reject_backdating(&req, admin)?;
+            todo_from_request(req)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    state.db.insert_batch(&todos)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(todos.into_iter().map(TodoResponse::from).collect()),
+    ))
+}
+
+async fn delete_todos_bulk(
+    State(state): State<AppState>,
+    JsonBody(ids): JsonBody<Vec<Uuid>>,
+) -> Result<Json<BulkDeleteResponse>, AppError> {
+    info!(count = ids.len(), "Deleting todos in bulk");
+    let existed = state.db.delete_batch(&ids)?;
+    let mut response = BulkDeleteResponse {
+        deleted: Vec::new(),
+        not_found: Vec::new(),
+    };
+    for (id, existed) in ids.into_iter().zip(existed) {
+        if existed {
+            response.deleted.push(id);
+        } else {
+            response.not_found.push(id);
+        }
+    }
+    Ok(Json(response))
+}
+
+async fn import_todos(
+    State(state): State<AppState>,
+    admin: Option<AdminClaim>,
+    Query(params): Query<ImportQuery>,
+    JsonBody(reqs): JsonBody<Vec<CreateTodoRequest>>,
+) -> Result<Json<ImportResult>, AppError> {
+    info!(
+        count = reqs.len(),
+        skip_duplicates = params.skip_duplicates,
+        "Importing todos"
     );
-    state.db.insert(&todo)?;
-    Ok((StatusCode::CREATED, Json(todo_to_response(todo))))
+    // Validate the whole batch up front so a bad item doesn't leave a partial import
+    let todos = reqs
+        .into_iter()
+        .map(|req| {
+            reject_backdating(&req, admin)?;
+            todo_from_request(req)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    import_batch(&state, todos, params.skip_duplicates).await
 }
 
-async fn get_todo(
+async fn import_opml(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
-) -> Result<Json<TodoResponse>, AppError> {
-    info!(%id, "Fetching todo");
+    Query(params): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<ImportResult>, AppError> {
+    info!(
+        skip_duplicates = params.skip_duplicates,
+        "Importing todos from OPML"
+    );
+    let todos = parse_opml(&body).map_err(|err| AppError::BadRequest(format!("{:#}", err)))?;
+    import_batch(&state, todos, params.skip_duplicates).await
+}
+
+async fn import_batch(
+    state: &AppState,
+    todos: Vec<Todo>,
+    skip_duplicates: bool,
+) -> Result<Json<ImportResult>, AppError> {
+    let mut imported = Vec::with_capacity(todos.len());
+    let mut skipped = Vec::new();
+    // Lowercased titles accepted so far, as the batch is only stored at the end
+    let mut batch_titles = HashSet::new();
+    for todo in todos {
+        let title = todo.title.to_lowercase();
+        if skip_duplicates
+            && (batch_titles.contains(&title) || state.db.contains_title(&todo.title)?)
+        {
+            skipped.push(todo.title);
+        } else {
+            batch_titles.insert(title);
+            imported.push(todo);
+        }
+    }
+    state.db.insert_batch(&imported)?;
+    Ok(Json(ImportResult {
+        imported: imported.into_iter().map(TodoResponse::from).collect(),
+        skipped,
+    }))
+}
+
+/// Backdating is reserved for migrations, which go through the import endpoint
+/// Runs a blocking [`TodoStorage`] call on tokio's blocking pool, like the
+/// `*_async` methods of [`TodoDb`] but for any backend
+async fn run_storage<S, T, F>(db: &S, f: F) -> anyhow::Result<T>
+where
+    S: TodoStorage + Clone + Send + Sync + 'static,
+    T: Send + 'static,
+    F: FnOnce(S) -> anyhow::Result<T> + Send + 'static,
+{
+    let db = db.clone();
+    tokio::task::spawn_blocking(move || f(db))
+        .await
+        .context("Database task failed")?
+}
+
+/// Backdating a todo through `created_at` is reserved for admins, on every
+/// route including imports
+fn reject_backdating(req: &CreateTodoRequest, admin: Option<AdminClaim>) -> Result<(), AppError> {
+    if req.created_at.is_some() && admin.is_none() {
+        return Err(AppError::Forbidden(
+            "created_at can only be set with an admin token".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn todo_from_request(req: CreateTodoRequest) -> Result<Todo, AppError> {
+    if let Some(metadata) = &req.metadata {
+        validate_metadata(metadata)?;
+    }
+    if let Some(hours) = req.estimate_hours {
+        validate_estimate(hours)?;
+    }
+    Ok(req.into())
+}
+
+async fn random_todo(State(state): State<AppState>) -> Result<Json<TodoResponse>, AppError> {
+    info!("Picking a random todo");
     let todo = state
         .db
-        .get(&id)?
+        .get_random_weighted()?
+        .ok_or_else(|| AppError::NotFound("No incomplete todos".to_string()))?;
+    Ok(Json(todo.into()))
+}
+
+async fn export_todos(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> impl IntoResponse {
+    info!(format = ?query.format, "Exporting todos");
+    let (content_type, disposition) = match query.format {
+        ExportFormat::Json => ("application/json", "attachment; filename=\"todos.json\""),
+        ExportFormat::Ics => ("text/calendar", "attachment; filename=\"todos.ics\""),
+        ExportFormat::Opml => ("text/x-opml", "attachment; filename=\"todos.opml\""),
+        ExportFormat::TodoTxt => ("text/plain", "attachment; filename=\"todo.txt\""),
+    };
+    let (tx, rx) = mpsc::channel(EXPORT_CHANNEL_CHUNKS);
+    let db = state.db.clone();
+    // sled and the writer are blocking, so the export runs off the async runtime
+    tokio::task::spawn_blocking(move || {
+        let mut writer = BufWriter::new(ChannelWriter(tx.clone()));
+        let result = match query.format {
+            ExportFormat::Json => db.export_to_json_writer(&mut writer),
+            ExportFormat::Ics => db.export_to_ics_writer(&mut writer),
+            ExportFormat::Opml => db.export_to_opml_writer(&mut writer),
+            ExportFormat::TodoTxt => db.export_to_todo_txt_writer(&mut writer),
+        };
+        let result = result.and_then(|count| {
+            writer.flush()?;
+            Ok(count)
+        });
+        match result {
+            Ok(count) => info!(count, "Exported todos"),
+            Err(err) => {
+                error!(error = %err, "failed to export todos");
+                drop(writer);
+                // Abort the body so the client doesn't mistake it for a complete export
+                tx.blocking_send(Err(io::Error::other(err.to_string())))
+                    .ok();
+            }
+        }
+    });
+
+    let body = stream::unfold(rx, |mut rx| async move {
+        let chunk = rx.recv().await?;
+        Some((chunk, rx))
+    });
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(body),
+    )
+}
+
+/// Forwards everything written to it to the export response body
+struct ChannelWriter(mpsc::Sender<io::Result<Vec<u8>>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "export client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn stats(State(state): State<AppState>) -> Result<Json<StatsResponse>, AppError> {
+    info!("Computing stats");
+    let summary = state.db.summary()?;
+    Ok(Json(StatsResponse {
+        total: summary.total,
+        active: summary.total - summary.completed,
+        completed: summary.completed,
+        overdue: summary.overdue,
+        total_estimated_hours: summary.total_estimated_hours,
+    }))
+}
+
+async fn timeline_stats(
+    State(state): State<AppState>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<Vec<TimelineBucket>>, AppError> {
+    let to = query.to.unwrap_or_else(chrono::Utc::now);
+    let from = query
+        .from
+        .unwrap_or_else(|| to - chrono::Duration::days(DEFAULT_TIMELINE_DAYS));
+    info!(%from, %to, granularity = ?query.granularity, "Computing timeline stats");
+    if from >= to {
+        return Err(AppError::BadRequest(
+            "Timeline 'from' must be before 'to'".to_string(),
+        ));
+    }
+
+    let buckets = state
+        .db
+        .timeline_stats(from, to, granularity_to_model(query.granularity))?
+        .into_iter()
+        .map(|bucket| TimelineBucket {
+            period: bucket.period,
+            created: bucket.created,
+            completed: bucket.completed,
+        })
+        .collect();
+    Ok(Json(buckets))
+}
+
+async fn get_todo<S>(
+    State(state): State<AppState<S>>,
+    Path(id): Path<Uuid>,
+) -> Result<([(header::HeaderName, &'static str); 1], Json<TodoResponse>), AppError>
+where
+    S: TodoStorage + Clone + Send + Sync + 'static,
+{
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Fetching todo");
+    let todo = run_storage(&state.db, move |db| db.get(&id))
+        .await?
         .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
-    Ok(Json(todo_to_response(todo)))
+    // Lets polling clients and caches reuse the response briefly, except for
+    // completed todos, which are often reopened right away
+    let cache_control = if todo.completed {
+        "no-store"
+    } else {
+        TODO_CACHE_CONTROL
+    };
+    Ok((
+        [(header::CACHE_CONTROL, cache_control)],
+        Json(TodoResponse::from(todo)),
+    ))
 }
 
 async fn update_todo(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    Json(req): Json<UpdateTodoRequest>,
+    JsonBody(req): JsonBody<UpdateTodoRequest>,
 ) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
     info!(%id, "Updating todo");
+    if let Some(metadata) = &req.metadata {
+        validate_metadata(metadata)?;
+    }
+    if let Some(Some(hours)) = req.estimate_hours {
+        validate_estimate(hours)?;
+    }
     let mut todo = state
         .db
-        .get(&id)?
+        .get_async(id)
+        .await?
         .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
-
     // Update fields
     todo.update(
         req.title,
         req.description,
         req.due_date,
-        req.priority.map(priority_to_model),
+        req.priority.map(Into::into),
+        req.metadata,
+        req.estimate_hours,
     );
 
     // Handle completed status separately
@@ -128,16 +878,44 @@ async fn update_todo(
         }
     }
 
-    state.db.update(&todo)?;
-    Ok(Json(todo_to_response(todo)))
+    if let Some(progress) = req.progress {
+        todo.set_progress(progress)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    }
+
+    if let Some(tags) = req.tags {
+        todo.set_tags(tags);
+    }
+
+    // The version is checked in the same transaction as the write, so two
+    // edits of the same version cannot both succeed
+    let todo = match req.expected_version {
+        Some(expected) => state
+            .db
+            .update_if_version_async(todo, expected)
+            .await
+            .map_err(|err| match err.downcast::<VersionConflict>() {
+                Ok(conflict) => AppError::Conflict(format!(
+                    "Todo with id {} is at version {}, not {}",
+                    id, conflict.current, conflict.expected
+                )),
+                Err(err) => err.into(),
+            })?,
+        None => state.db.update_async(todo).await?,
+    };
+    Ok(Json(todo.into()))
 }
 
-async fn delete_todo(
-    State(state): State<AppState>,
+async fn delete_todo<S>(
+    State(state): State<AppState<S>>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, AppError> {
+) -> Result<StatusCode, AppError>
+where
+    S: TodoStorage + Clone + Send + Sync + 'static,
+{
+    Span::current().record("todo.id", id.to_string());
     info!(%id, "Deleting todo");
-    let existed = state.db.delete(&id)?;
+    let existed = run_storage(&state.db, move |db| db.delete(&id)).await?;
     if existed {
         Ok(StatusCode::NO_CONTENT)
     } else {
@@ -145,34 +923,429 @@ async fn delete_todo(
     }
 }
 
+async fn snooze_todo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    JsonBody(req): JsonBody<SnoozeRequest>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Snoozing todo");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    match req {
+        SnoozeRequest::Hours { hours } if hours > 0 => todo.snooze(chrono::Duration::hours(hours)),
+        SnoozeRequest::Hours { hours } => {
+            return Err(AppError::BadRequest(format!(
+                "Snooze hours must be positive, got {}",
+                hours
+            )));
+        }
+        SnoozeRequest::Until { until } => {
+            todo.update(None, None, Some(Some(until)), None, None, None)
+        }
+    }
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn pin_todo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    req: Option<JsonBody<PinRequest>>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Pinning todo");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    let pinned = req
+        .and_then(|JsonBody(req)| req.pinned)
+        .unwrap_or(!todo.pinned);
+    todo.set_pinned(pinned);
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn duplicate_todo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<TodoResponse>), AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Duplicating todo");
+    let todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    let copy = todo.clone_with_new_id();
+    state.db.insert_async(copy.clone()).await?;
+    Ok((StatusCode::CREATED, Json(copy.into())))
+}
+
+async fn related_todos(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<RelatedTodoResponse>>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Listing related todos");
+    state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    let related = state.db.get_related(&id, RELATED_TODOS_LIMIT)?;
+    Ok(Json(
+        related
+            .into_iter()
+            .map(|(todo, shared_tags)| RelatedTodoResponse {
+                todo: todo.into(),
+                shared_tags,
+            })
+            .collect(),
+    ))
+}
+
+async fn set_todo_priority(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    JsonBody(req): JsonBody<PriorityRequest>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, priority = ?req.priority, "Setting todo priority");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    todo.update(None, None, None, Some(req.priority.into()), None, None);
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn set_todo_due_date(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    JsonBody(req): JsonBody<DueDateRequest>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, due_date = ?req.due_date, "Setting todo due date");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    todo.update(None, None, Some(req.due_date), None, None, None);
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn complete_todo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Completing todo");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+    if todo.completed {
+        return Err(AppError::Conflict(format!(
+            "Todo with id {} is already completed",
+            id
+        )));
+    }
+
+    todo.mark_completed();
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn reopen_todo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Reopening todo");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+    if !todo.completed {
+        return Err(AppError::Conflict(format!(
+            "Todo with id {} is not completed",
+            id
+        )));
+    }
+
+    todo.mark_incomplete();
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn start_todo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TodoResponse>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Starting todo");
+    let mut todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+
+    todo.start();
+
+    let todo = state.db.update_async(todo).await?;
+    Ok(Json(todo.into()))
+}
+
+async fn todo_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Subscribing to todo events");
+    if !state.db.exists_async(id).await? {
+        return Err(AppError::NotFound(format!("Todo with id {} not found", id)));
+    }
+
+    let db = state.db.clone();
+    let events = stream::unfold(state.db.watch_todo(&id), |mut subscriber| async move {
+        let event = (&mut subscriber).await?;
+        Some((event, subscriber))
+    })
+    .filter_map(move |event| {
+        let payload = match event {
+            // The stored value is re-read so the event carries the complete todo
+            sled::Event::Insert { .. } => match db.get(&id) {
+                Ok(todo) => todo.map(|todo| TodoEvent::Updated { todo: todo.into() }),
+                Err(err) => {
+                    error!(error = %err, %id, "failed to load todo for event");
+                    None
+                }
+            },
+            sled::Event::Remove { .. } => Some(TodoEvent::Deleted { id }),
+        };
+        future::ready(payload.map(|payload| Event::default().json_data(payload)))
+    })
+    .take_until(state.shutdown_token.clone().cancelled_owned());
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn all_todo_events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    info!("Subscribing to all todo changes");
+    let events = stream::unfold(state.db.watch_all(), |mut changes| async move {
+        changes.changed().await.ok()?;
+        let counter = *changes.borrow_and_update();
+        Some((counter, changes))
+    })
+    .map(|counter| Event::default().json_data(TodoEvent::AnyChange { counter }))
+    .take_until(state.shutdown_token.clone().cancelled_owned());
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+async fn get_todo_metadata(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Fetching todo metadata");
+    let todo = state
+        .db
+        .get_async(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+    Ok(Json(todo.metadata))
+}
+
+async fn patch_todo_metadata(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    JsonBody(patch): JsonBody<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    Span::current().record("todo.id", id.to_string());
+    info!(%id, "Patching todo metadata");
+    if !patch.is_object() {
+        return Err(AppError::BadRequest(
+            "Metadata patch must be a JSON object".to_string(),
+        ));
+    }
+    // The merged result is validated before it's stored
+    let metadata = state
+        .db
+        .update_metadata_async(id, patch, MAX_METADATA_BYTES)
+        .await
+        .map_err(|err| match err.downcast::<MetadataTooLarge>() {
+            Ok(too_large) => AppError::BadRequest(too_large.to_string()),
+            Err(err) => err.into(),
+        })?
+        .ok_or_else(|| AppError::NotFound(format!("Todo with id {} not found", id)))?;
+    Ok(Json(metadata))
+}
+
+async fn set_notification_preference(
+    State(state): State<AppState>,
+    JsonBody(req): JsonBody<NotificationPreference>,
+) -> Result<Json<NotificationPreference>, AppError> {
+    info!(user = %req.user, "Setting notification preference");
+    if req.user.trim().is_empty() {
+        return Err(AppError::BadRequest("User cannot be empty".to_string()));
+    }
+    let preference = ModelNotificationPreference {
+        user: req.user.clone(),
+        channel: notification_channel_to_model(req.channel.clone()),
+        advance_hours: req.advance_hours,
+    };
+    state.db.set_notification_preference(&preference)?;
+    Ok(Json(req))
+}
+
+async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let db_ok = match state.db.ping() {
+        Ok(()) => true,
+        Err(err) => {
+            error!(error = %err, "health check could not read the database");
+            false
+        }
+    };
+    let (status, label) = if db_ok {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    (
+        status,
+        Json(HealthResponse {
+            status: label.to_string(),
+            db_ok,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: state.started_at.elapsed().as_secs(),
+        }),
+    )
+}
+
+async fn reindex(State(state): State<AppState>) -> Result<Json<ReindexResponse>, AppError> {
+    info!("Rebuilding secondary indexes");
+    let report = state.db.reindex()?;
+    info!(
+        records = report.records_processed,
+        indexes = ?report.indexes_rebuilt,
+        "Rebuilt secondary indexes"
+    );
+    Ok(Json(ReindexResponse {
+        records_processed: report.records_processed,
+        indexes_rebuilt: report.indexes_rebuilt,
+    }))
+}
+
+async fn storage(State(state): State<AppState>) -> Result<Json<StorageResponse>, AppError> {
+    info!("Measuring database storage");
+    let size_bytes = state.db.size_on_disk()?;
+    Ok(Json(StorageResponse {
+        size_bytes,
+        size_human: bytes_to_human(size_bytes),
+        record_count: state.db.count()?,
+    }))
+}
+
 // Helper functions
 
-fn todo_to_response(todo: Todo) -> TodoResponse {
-    TodoResponse {
-        id: todo.id,
-        title: todo.title,
-        description: todo.description,
-        due_date: todo.due_date,
-        priority: model_priority_to_transfer(todo.priority),
-        completed: todo.completed,
-        created_at: todo.created_at,
-        updated_at: todo.updated_at,
+/// Formats a byte count with binary units, e.g. 1048576 as "1.0 MB"
+fn bytes_to_human(n: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if n < 1024 {
+        return format!("{} B", n);
+    }
+    let mut size = n as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
     }
+    format!("{:.1} {}", size, UNITS[unit])
 }
 
-fn priority_to_model(priority: Priority) -> ModelPriority {
-    match priority {
-        Priority::Low => ModelPriority::Low,
-        Priority::Medium => ModelPriority::Medium,
-        Priority::High => ModelPriority::High,
+fn parse_ids(ids: &str) -> Result<Vec<Uuid>, AppError> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            Uuid::parse_str(id)
+                .map_err(|_| AppError::BadRequest(format!("Invalid todo id: {}", id)))
+        })
+        .collect()
+}
+
+fn validate_metadata(metadata: &serde_json::Value) -> Result<(), AppError> {
+    MetadataTooLarge::check(metadata, MAX_METADATA_BYTES)
+        .map_err(|too_large| AppError::BadRequest(too_large.to_string()))
+}
+
+fn validate_estimate(hours: f32) -> Result<(), AppError> {
+    if !hours.is_finite() || hours < 0.0 {
+        return Err(AppError::BadRequest(format!(
+            "Estimate must be a non-negative number of hours, got {}",
+            hours
+        )));
+    }
+    Ok(())
+}
+
+fn granularity_to_model(granularity: Granularity) -> ModelGranularity {
+    match granularity {
+        Granularity::Hour => ModelGranularity::Hour,
+        Granularity::Day => ModelGranularity::Day,
+        Granularity::Week => ModelGranularity::Week,
+        Granularity::Month => ModelGranularity::Month,
+    }
+}
+
+fn sort_by_to_model(sort_by: SortBy) -> ModelSortBy {
+    match sort_by {
+        SortBy::CreatedAt => ModelSortBy::CreatedAt,
+        SortBy::UpdatedAt => ModelSortBy::UpdatedAt,
+        SortBy::DueDate => ModelSortBy::DueDate,
+        SortBy::Priority => ModelSortBy::Priority,
+        SortBy::Title => ModelSortBy::Title,
     }
 }
 
-fn model_priority_to_transfer(priority: ModelPriority) -> Priority {
-    match priority {
-        ModelPriority::Low => Priority::Low,
-        ModelPriority::Medium => Priority::Medium,
-        ModelPriority::High => Priority::High,
+fn sort_direction_to_model(dir: SortDirection) -> ModelSortDirection {
+    match dir {
+        SortDirection::Asc => ModelSortDirection::Asc,
+        SortDirection::Desc => ModelSortDirection::Desc,
+    }
+}
+
+fn notification_channel_to_model(channel: NotificationChannel) -> ModelNotificationChannel {
+    match channel {
+        NotificationChannel::Email(address) => ModelNotificationChannel::Email(address),
+        NotificationChannel::Webhook(url) => ModelNotificationChannel::Webhook(url),
     }
 }
 
@@ -181,27 +1354,802 @@ fn model_priority_to_transfer(priority: ModelPriority) -> Priority {
 enum AppError {
     DatabaseError(anyhow::Error),
     NotFound(String),
+    BadRequest(String),
+    /// Missing or invalid credentials (401)
+    Unauthorized(String),
+    /// Credentials don't allow the request (403)
+    Forbidden(String),
+    /// Request conflicts with the current state of the resource (409)
+    Conflict(String),
+    /// Well-formed request whose content could not be accepted (422)
+    Validation(Vec<String>),
+    Timeout,
+    Internal(String),
 }
 
+async fn handle_middleware_error(err: BoxError) -> AppError {
+    if err.is::<tower::timeout::error::Elapsed>() {
+        AppError::Timeout
+    } else {
+        AppError::Internal(err.to_string())
+    }
+}
+
+// Only the database layer produces anyhow errors. The JSON errors it wraps
+// come from stored metadata and export writes, never from client input, so
+// they rightly end up as 500s.
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         AppError::DatabaseError(err)
     }
 }
 
+// Handlers only run `serde_json::from_*` on what the client sent, so a
+// failure there is the client's to fix
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Validation(vec![err.to_string()])
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::Validation(vec![rejection.body_text()])
+    }
+}
+
+/// `Json` for request bodies, rejecting malformed ones with a 422
+/// [`ErrorResponse`] rather than axum's plain-text rejection.
+struct JsonBody<T>(T);
+
+impl<S, T> FromRequest<S> for JsonBody<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(JsonBody(value))
+    }
+}
+
+impl<S, T> OptionalFromRequest<S> for JsonBody<T>
+where
+    Json<T>: OptionalFromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        let value = <Json<T> as OptionalFromRequest<S>>::from_request(req, state).await?;
+        Ok(value.map(|Json(value)| JsonBody(value)))
+    }
+}
+
+// Not logged here: `log_error_responses` logs every error response once
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
-            AppError::DatabaseError(err) => {
-                error!(error = %err, "database error while handling request");
-                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            AppError::DatabaseError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::Validation(errors) => (StatusCode::UNPROCESSABLE_ENTITY, errors.join("; ")),
+            AppError::Timeout => (StatusCode::GATEWAY_TIMEOUT, "Request timed out".to_string()),
+            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        };
+
+        (status, Json(ErrorResponse::new(message))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Method, Request};
+    use std::time::Duration;
+    use todoapp_model::{MemoryTodoDb, Priority as ModelPriority};
+    use tower::ServiceExt;
+
+    /// An app over a fresh database in a temporary directory, configured with
+    /// the defaults rather than the environment. Keep the directory alive for
+    /// as long as the database is used.
+    fn test_app() -> (tempfile::TempDir, TodoDb, Router) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = TodoDb::new(temp_dir.path()).unwrap();
+        let config = Config {
+            jwt_key: Some(test_jwt_key()),
+            ..Config::default()
+        };
+        let app = build_app(AppState::new(db.clone(), config));
+        (temp_dir, db, app)
+    }
+
+    fn test_jwt_key() -> ring::hmac::Key {
+        ring::hmac::Key::new(ring::hmac::HMAC_SHA256, b"test secret")
+    }
+
+    /// `Authorization` value accepted as an admin by [`test_app`]
+    fn admin_bearer() -> String {
+        let token = auth::sign(&serde_json::json!({ "admin": true }), &test_jwt_key());
+        format!("Bearer {}", token)
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_token_stops_server() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let state = AppState::new(TodoDb::new(temp_dir.path()).unwrap(), config.clone());
+        let shutdown_token = state.shutdown_token.clone();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = tokio::spawn(serve(
+            listener,
+            build_app(state),
+            shutdown_token.clone(),
+            config.shutdown_timeout(),
+        ));
+
+        shutdown_token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .expect("server did not stop within 1 second")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drops_requests_after_drain_timeout() {
+        let app = Router::new().route("/slow", get(|| tokio::time::sleep(Duration::from_secs(60))));
+        let shutdown_token = CancellationToken::new();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(
+            listener,
+            app,
+            shutdown_token.clone(),
+            Duration::from_millis(100),
+        ));
+
+        tokio::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        // Let the request reach the handler before shutting down
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        shutdown_token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .expect("server did not stop after the drain timeout")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_trailing_slash_redirects() {
+        let (_temp_dir, _db, app) = test_app();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/todos/?sort=priority_desc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "/api/todos?sort=priority_desc"
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/todos")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_todo_sets_cache_control() {
+        let (_temp_dir, db, app) = test_app();
+        let mut todo = Todo::new("Cached".to_string(), None, None, ModelPriority::Low);
+        db.insert(&todo).unwrap();
+        let id = todo.id;
+        let request = move || {
+            Request::builder()
+                .uri(format!("/api/todos/{}", id))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            TODO_CACHE_CONTROL
+        );
+
+        todo.mark_completed();
+        db.update(&todo).unwrap();
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sigterm_stops_server() {
+        use nix::sys::signal::{Signal, kill};
+        use nix::unistd::Pid;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let state = AppState::new(TodoDb::new(temp_dir.path()).unwrap(), config.clone());
+        let shutdown_token = state.shutdown_token.clone();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = tokio::spawn(serve(
+            listener,
+            build_app(state),
+            shutdown_token.clone(),
+            config.shutdown_timeout(),
+        ));
+        tokio::spawn(cancel_on_signal(shutdown_token));
+        // Let the signal handler register before the signal is sent
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        kill(Pid::this(), Signal::SIGTERM).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .expect("server did not stop within 1 second")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_malformed_body_is_unprocessable() {
+        let (_temp_dir, _db, app) = test_app();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/todos")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"title": "#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(!error.error.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_json_error_is_unprocessable() {
+        let err = serde_json::from_str::<CreateTodoRequest>(r#"{"title": 1}"#).unwrap_err();
+        let response = AppError::from(err).into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error.error.contains("invalid type"));
+    }
+
+    #[tokio::test]
+    async fn test_storage_routes_serve_memory_backend() {
+        let app =
+            storage_routes().with_state(AppState::new(MemoryTodoDb::new(), Config::default()));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/todos")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"title": "In memory"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: TodoResponse = serde_json::from_slice(&body).unwrap();
+
+        let get = |id: Uuid| {
+            Request::builder()
+                .uri(format!("/todos/{}", id))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(get(created.id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/todos/{}", created.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app.oneshot(get(created.id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_bytes_to_human() {
+        assert_eq!(bytes_to_human(512), "512 B");
+        assert_eq!(bytes_to_human(1536), "1.5 KB");
+        assert_eq!(bytes_to_human(1_048_576), "1.0 MB");
+        assert_eq!(bytes_to_human(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+
+    #[tokio::test]
+    async fn test_related_todos() {
+        let (_temp_dir, db, app) = test_app();
+        let mut target = Todo::new("Target".to_string(), None, None, ModelPriority::Low);
+        target.set_tags(vec!["home".to_string()]);
+        let mut related = Todo::new("Related".to_string(), None, None, ModelPriority::Low);
+        related.set_tags(vec!["home".to_string(), "work".to_string()]);
+        let unrelated = Todo::new("Unrelated".to_string(), None, None, ModelPriority::Low);
+        for todo in [&target, &related, &unrelated] {
+            db.insert(todo).unwrap();
+        }
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/todos/{}/related", target.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let todos: Vec<RelatedTodoResponse> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].todo.id, related.id);
+        assert_eq!(todos[0].shared_tags, 1);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/todos/{}/related", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_json_export_imports_again() {
+        let (_temp_dir, db, app) = test_app();
+        let mut todo = Todo::new("Exported".to_string(), None, None, ModelPriority::High);
+        todo.metadata = serde_json::json!({ "source": "mail" });
+        todo.set_tags(vec!["home".to_string()]);
+        db.insert(&todo).unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/todos/export?format=json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let export = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let (_other_dir, other_db, other_app) = test_app();
+        // Exports carry created_at, so importing them takes an admin token
+        let import = |authorization: Option<String>| {
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri("/api/todos/import")
+                .header(header::CONTENT_TYPE, "application/json");
+            if let Some(authorization) = authorization {
+                request = request.header(header::AUTHORIZATION, authorization);
             }
-            AppError::NotFound(msg) => {
-                error!(message = %msg, "resource not found");
-                (StatusCode::NOT_FOUND, msg)
+            request.body(Body::from(export.clone())).unwrap()
+        };
+        let response = other_app.clone().oneshot(import(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(other_db.get_all().unwrap().is_empty());
+
+        let response = other_app
+            .oneshot(import(Some(admin_bearer())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let imported = other_db.get_all().unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].title, "Exported");
+        assert_eq!(imported[0].created_at, todo.created_at);
+        assert_eq!(imported[0].priority, ModelPriority::High);
+        assert_eq!(imported[0].metadata, todo.metadata);
+        assert_eq!(imported[0].tags, todo.tags);
+    }
+
+    #[tokio::test]
+    async fn test_backdating_needs_admin_token() {
+        let (_temp_dir, _db, app) = test_app();
+        let created_at = "2020-01-01T00:00:00Z";
+        let body = format!(r#"{{"title": "Old", "created_at": "{}"}}"#, created_at);
+        let create = |authorization: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/todos")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, authorization)
+                .body(Body::from(body.clone()))
+                .unwrap()
+        };
+
+        let user_token = auth::sign(&serde_json::json!({ "admin": false }), &test_jwt_key());
+        let response = app
+            .clone()
+            .oneshot(create(&format!("Bearer {}", user_token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(create("Bearer not.a.token"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(create(&admin_bearer())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: TodoResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created.created_at.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_set_due_date() {
+        let (_temp_dir, db, app) = test_app();
+        let due = chrono::Utc::now();
+        let todo = Todo::new("Dated".to_string(), None, Some(due), ModelPriority::Low);
+        db.insert(&todo).unwrap();
+        let request = |body: &'static str| {
+            Request::builder()
+                .method(Method::PATCH)
+                .uri(format!("/api/todos/{}/due-date", todo.id))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // Leaving the field out is a mistake, not a request to clear the date
+        for body in ["{}", r#"{"due_date": "tomorrow"}"#] {
+            let response = app.clone().oneshot(request(body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        assert!(db.get(&todo.id).unwrap().unwrap().due_date.is_some());
+
+        let response = app
+            .clone()
+            .oneshot(request(r#"{"due_date": "2025-03-01T00:00:00Z"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored = db.get(&todo.id).unwrap().unwrap();
+        assert_eq!(
+            stored.due_date.unwrap().to_rfc3339(),
+            "2025-03-01T00:00:00+00:00"
+        );
+
+        let response = app.oneshot(request(r#"{"due_date": null}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(db.get(&todo.id).unwrap().unwrap().due_date.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_patch_metadata() {
+        let (_temp_dir, db, app) = test_app();
+        let mut todo = Todo::new("Tagged".to_string(), None, None, ModelPriority::Low);
+        todo.metadata = serde_json::json!({ "a": 1, "b": 2 });
+        db.insert(&todo).unwrap();
+        let request = |id: Uuid| {
+            Request::builder()
+                .method(Method::PATCH)
+                .uri(format!("/api/todos/{}/metadata", id))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"a": null, "c": 3}"#))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request(todo.id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored = db.get(&todo.id).unwrap().unwrap();
+        assert_eq!(stored.metadata, serde_json::json!({ "b": 2, "c": 3 }));
+        assert_eq!(stored.version, todo.version + 1);
+
+        let response = app.oneshot(request(Uuid::new_v4())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_import_skips_duplicates_within_batch() {
+        let (_temp_dir, db, app) = test_app();
+        db.insert(&Todo::new(
+            "Existing".to_string(),
+            None,
+            None,
+            ModelPriority::Low,
+        ))
+        .unwrap();
+
+        let body = serde_json::json!([
+            { "title": "Existing", "priority": "Low" },
+            { "title": "Water plants", "priority": "Low" },
+            { "title": "water plants", "priority": "High" },
+        ]);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/todos/import?skip_duplicates=true")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let result: ImportResult = serde_json::from_slice(&body).unwrap();
+        assert_eq!(result.imported.len(), 1);
+        assert_eq!(result.imported[0].title, "Water plants");
+        assert_eq!(result.skipped, ["Existing", "water plants"]);
+        assert_eq!(db.count().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_limit_alone_returns_plain_array() {
+        let (_temp_dir, db, app) = test_app();
+        for title in ["a", "b", "c"] {
+            db.insert(&Todo::new(
+                title.to_string(),
+                None,
+                None,
+                ModelPriority::Low,
+            ))
+            .unwrap();
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/todos?limit=2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[TOTAL_COUNT_HEADER], "3");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let todos: Vec<TodoResponse> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(todos.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_filters_by_creation_time() {
+        use chrono::TimeZone;
+
+        let (_temp_dir, db, app) = test_app();
+        let day = |d| chrono::Utc.with_ymd_and_hms(2025, 1, d, 12, 0, 0).unwrap();
+        for (title, created) in [
+            ("Before", day(1)),
+            ("First", day(10)),
+            ("Last", day(19)),
+            ("After", day(20)),
+        ] {
+            db.insert(&Todo::new_with_timestamps(
+                title.to_string(),
+                None,
+                None,
+                ModelPriority::Low,
+                created,
+                created,
+            ))
+            .unwrap();
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(
+                        "/api/todos?created_after=2025-01-10T12:00:00Z\
+                         &created_before=2025-01-20T12:00:00Z",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[TOTAL_COUNT_HEADER], "2");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let todos: Vec<TodoResponse> = serde_json::from_slice(&body).unwrap();
+        let mut titles: Vec<&str> = todos.iter().map(|todo| todo.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["First", "Last"]);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination_walks_all_todos() {
+        let (_temp_dir, db, app) = test_app();
+        for title in ["a", "b", "c"] {
+            db.insert(&Todo::new(
+                title.to_string(),
+                None,
+                None,
+                ModelPriority::Low,
+            ))
+            .unwrap();
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        let mut titles = Vec::new();
+        let mut uri = "/api/todos?paginate=cursor&limit=2&sort_by=title".to_string();
+        loop {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let page: PaginatedTodosResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(page.total, 3);
+            titles.extend(page.items.into_iter().map(|todo| todo.title));
+            match page.next_cursor {
+                Some(cursor) => uri = format!("/api/todos?limit=2&sort_by=title&after={}", cursor),
+                None => break,
             }
+        }
+        assert_eq!(titles, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination_filters_before_paging() {
+        let (_temp_dir, db, app) = test_app();
+        for title in ["a", "b", "c", "d", "e"] {
+            let mut todo = Todo::new(title.to_string(), None, None, ModelPriority::Low);
+            if title == "b" || title == "d" {
+                todo.mark_completed();
+            }
+            db.insert(&todo).unwrap();
+        }
+
+        let mut pages = Vec::new();
+        let mut uri = "/api/todos?paginate=cursor&limit=1&sort_by=title&completed=true".to_string();
+        loop {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let page: PaginatedTodosResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(page.total, 2);
+            pages.push(
+                page.items
+                    .into_iter()
+                    .map(|todo| todo.title)
+                    .collect::<Vec<_>>(),
+            );
+            match page.next_cursor {
+                Some(cursor) => {
+                    uri = format!(
+                        "/api/todos?limit=1&sort_by=title&completed=true&after={}",
+                        cursor
+                    )
+                }
+                None => break,
+            }
+        }
+        // Pages are filled from further down rather than coming back empty
+        assert_eq!(pages[0], ["b"]);
+        assert_eq!(pages.concat(), ["b", "d"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_with_stale_version_conflicts() {
+        let (_temp_dir, db, app) = test_app();
+        let todo = Todo::new("Versioned".to_string(), None, None, ModelPriority::Low);
+        db.insert(&todo).unwrap();
+        let id = todo.id;
+        let request = move |title: &str| {
+            let body = serde_json::json!({ "title": title, "expected_version": 1 });
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/todos/{}", id))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
         };
 
-        (status, Json(ErrorResponse::new(message))).into_response()
+        let response = app.clone().oneshot(request("First")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let updated: TodoResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(updated.version, 2);
+
+        let response = app.oneshot(request("Second")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_database() {
+        let (_temp_dir, _db, app) = test_app();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let health: HealthResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health.status, "ok");
+        assert!(health.db_ok);
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
     }
 }
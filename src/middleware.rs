@@ -0,0 +1,318 @@
+//! Layers applied to every request: correlation ids, trailing-slash
+//! redirects, request and error logging and the root tracing span.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, OriginalUri},
+    http::{HeaderValue, Method, Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Instant;
+use todoapp_transfer::ErrorResponse;
+use tracing::{Span, error, info, info_span, warn};
+use uuid::Uuid;
+
+/// Request and response header carrying the request's correlation id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Largest error body the error logging middleware buffers; larger ones
+/// are passed on untouched and logged without their content
+const MAX_LOGGED_ERROR_BYTES: usize = 64 * 1024;
+
+/// Polled by load balancers, too often to be worth a log line
+const HEALTH_PATH: &str = "/api/health";
+
+/// Correlation id of the current request, available to handlers as an extension
+#[derive(Debug, Clone, Copy)]
+pub struct RequestId(pub Uuid);
+
+/// Reuses the client's `X-Request-Id` if it's a UUID, otherwise generates one,
+/// and exposes it to handlers as `Extension<RequestId>`
+pub async fn assign_request_id(mut req: Request<Body>, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value).ok())
+        .unwrap_or_else(Uuid::new_v4);
+    req.extensions_mut().insert(RequestId(id));
+
+    let mut response = next.run(req).await;
+    let value = HeaderValue::try_from(id.to_string()).expect("UUIDs are valid header values");
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+/// Logs one line per request with its method, path, matched route, status
+/// and latency. The route is only known inside a [`Router`](axum::Router),
+/// so add this with `Router::layer`.
+pub async fn log_requests(req: Request<Body>, next: Next) -> Response {
+    let method = req.method().clone();
+    // Nested routers see the path without their prefix
+    let path = match req.extensions().get::<OriginalUri>() {
+        Some(OriginalUri(uri)) => uri.path().to_owned(),
+        None => req.uri().path().to_owned(),
+    };
+    if method == Method::GET && path == HEALTH_PATH {
+        return next.run(req).await;
+    }
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| route.as_str().to_owned());
+
+    let start = Instant::now();
+    let response = next.run(req).await;
+    info!(
+        %method,
+        %path,
+        route = route.as_deref(),
+        status = response.status().as_u16(),
+        elapsed_us = start.elapsed().as_micros() as u64,
+        "Request completed"
+    );
+    response
+}
+
+/// Redirects `/api/todos/` to `/api/todos`, since routes only match without
+/// the slash. GET and HEAD get a 301; other methods a 308 so the body is resent.
+pub async fn redirect_trailing_slash(req: Request<Body>, next: Next) -> Response {
+    let path = req.uri().path();
+    let canonical = path.trim_end_matches('/');
+    if !path.starts_with("/api/") || canonical == path || canonical == "/api" {
+        return next.run(req).await;
+    }
+    let location = match req.uri().query() {
+        Some(query) => format!("{}?{}", canonical, query),
+        None => canonical.to_string(),
+    };
+    let status = if req.method() == Method::GET || req.method() == Method::HEAD {
+        StatusCode::MOVED_PERMANENTLY
+    } else {
+        StatusCode::PERMANENT_REDIRECT
+    };
+    (status, [(header::LOCATION, location)]).into_response()
+}
+
+/// Logs 4xx and 5xx responses with structured fields, leaving successful
+/// requests to `TraceLayer`. This is the one place errors are logged.
+pub async fn log_error_responses(req: Request<Body>, next: Next) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.to_string());
+
+    let response = next.run(req).await;
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+    let log = |error_body: Option<&str>| {
+        if status.is_server_error() {
+            error!(
+                %method,
+                %path,
+                status = status.as_u16(),
+                request_id = request_id.as_deref(),
+                error_body,
+                "Request failed"
+            );
+        } else {
+            warn!(
+                %method,
+                %path,
+                status = status.as_u16(),
+                request_id = request_id.as_deref(),
+                error_body,
+                "Request rejected"
+            );
+        }
+    };
+
+    // Streams and large bodies go to the client as they are
+    let (parts, body) = response.into_parts();
+    let fits = body
+        .size_hint()
+        .upper()
+        .is_some_and(|len| len <= MAX_LOGGED_ERROR_BYTES as u64);
+    if !fits {
+        log(None);
+        return Response::from_parts(parts, body);
+    }
+
+    // The body has to be buffered to read it, then put back for the client
+    let bytes = match axum::body::to_bytes(body, MAX_LOGGED_ERROR_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!(
+                %method,
+                %path,
+                status = status.as_u16(),
+                error = %e,
+                "Failed to read error response body"
+            );
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let error_body = match serde_json::from_slice::<ErrorResponse>(&bytes) {
+        Ok(body) => body.error,
+        Err(_) => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    log(Some(&error_body));
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Root span for each request; handlers fill in `todo.id` once it's known
+pub fn make_request_span(req: &Request<Body>) -> Span {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.to_string());
+    info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        request_id = request_id.as_deref(),
+        todo.id = tracing::field::Empty,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Extension, Router, middleware, routing::get};
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    /// Collects formatted log output so tests can inspect it
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_extension_reaches_handlers() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|Extension(RequestId(id)): Extension<RequestId>| async move { id.to_string() }),
+            )
+            .layer(middleware::from_fn(assign_request_id));
+        let id = Uuid::new_v4();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(REQUEST_ID_HEADER, id.to_string())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers()[REQUEST_ID_HEADER],
+            id.to_string().as_str()
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, id.to_string().as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_request_id_is_replaced() {
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(middleware::from_fn(assign_request_id));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(REQUEST_ID_HEADER, "not-a-uuid")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(Uuid::parse_str(id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_log_requests_emits_one_line_per_request() {
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let api = Router::new()
+            .route("/todos/{id}", get(|| async {}))
+            .route("/health", get(|| async {}))
+            .layer(middleware::from_fn(log_requests));
+        let app = Router::new().nest("/api", api);
+        for uri in ["/api/todos/42", "/api/health"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1, "unexpected log output: {}", output);
+        assert!(lines[0].contains("Request completed"));
+        assert!(lines[0].contains("path=/api/todos/42"));
+        assert!(lines[0].contains("/api/todos/{id}"));
+        assert!(lines[0].contains("status=200"));
+        assert!(lines[0].contains("elapsed_us="));
+    }
+
+    #[tokio::test]
+    async fn test_large_error_body_reaches_client() {
+        let body = "x".repeat(MAX_LOGGED_ERROR_BYTES * 2);
+        let app = Router::new()
+            .route(
+                "/",
+                get({
+                    let body = body.clone();
+                    || async move { (StatusCode::BAD_REQUEST, body) }
+                }),
+            )
+            .layer(middleware::from_fn(log_error_responses));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let received = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(received, body.as_bytes());
+    }
+}
@@ -0,0 +1,72 @@
+// Each integration test binary uses a different subset of these helpers
+#![allow(dead_code)]
+
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Kills the spawned backend when the test finishes, even on panic
+pub struct TestServer {
+    child: Child,
+    port: u16,
+    _data_dir: tempfile::TempDir,
+}
+
+impl TestServer {
+    pub fn start() -> Self {
+        Self::start_with_env(&[])
+    }
+
+    /// Starts the server on a free port with extra environment variables and
+    /// waits until it accepts connections
+    pub fn start_with_env(vars: &[(&str, &str)]) -> Self {
+        let port = free_port();
+
+        // The server keeps its database under ./data, so run it from a scratch directory
+        let data_dir = tempfile::tempdir().unwrap();
+        let child = Command::new(env!("CARGO_BIN_EXE_todoapp"))
+            .current_dir(data_dir.path())
+            .envs(vars.iter().copied())
+            .env("TODOAPP_PORT", port.to_string())
+            .spawn()
+            .expect("Failed to start todoapp server");
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "server did not start in time");
+            sleep(Duration::from_millis(50));
+        }
+
+        Self {
+            child,
+            port,
+            _data_dir: data_dir,
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Base URL of the API, e.g. `http://127.0.0.1:41234/api`
+    pub fn api_base(&self) -> String {
+        format!("http://127.0.0.1:{}/api", self.port)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// A port nothing listens on right now. Test binaries run in parallel, so a
+/// fixed port would make their servers collide.
+fn free_port() -> u16 {
+    TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .expect("Failed to find a free port")
+        .port()
+}
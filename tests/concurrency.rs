@@ -0,0 +1,41 @@
+mod common;
+
+use common::TestServer;
+
+const REQUESTS: usize = 500;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn server_stays_responsive_under_burst() {
+    // A low limit forces most of the burst to queue behind the semaphore
+    let server = TestServer::start_with_env(&[("TODOAPP_MAX_CONCURRENCY", "8")]);
+    let client = reqwest::Client::new();
+    let url = format!("{}/todos", server.api_base());
+
+    let tasks: Vec<_> = (0..REQUESTS)
+        .map(|_| {
+            let client = client.clone();
+            let url = url.clone();
+            tokio::spawn(async move {
+                client
+                    .get(url)
+                    .send()
+                    .await
+                    .is_ok_and(|response| response.status() == reqwest::StatusCode::OK)
+            })
+        })
+        .collect();
+
+    let mut succeeded = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            succeeded += 1;
+        }
+    }
+
+    assert!(
+        succeeded * 100 >= REQUESTS * 95,
+        "only {} of {} requests succeeded",
+        succeeded,
+        REQUESTS
+    );
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::TestServer;
+use serde_json::Value;
+
+#[tokio::test]
+async fn server_uses_configured_port_and_cors_origin() {
+    let server = TestServer::start_with_env(&[
+        ("TODOAPP_HOST", "127.0.0.1"),
+        ("TODOAPP_CORS_ORIGIN", "https://todo.example.com"),
+        ("RUST_LOG", "warn"),
+    ]);
+
+    let response = reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{}/api/health", server.port()))
+        .header("origin", "https://todo.example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://todo.example.com"
+    );
+    let health: Value = response.json().await.unwrap();
+    assert_eq!(health["db_ok"], true);
+}
+
+#[test]
+fn server_refuses_invalid_port() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_todoapp"))
+        .env("TODOAPP_PORT", "not-a-port")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("TODOAPP_PORT"));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::TestServer;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn script(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("examples/http")
+        .join(name)
+}
+
+fn run(server: &TestServer, name: &str, args: &[&str]) -> String {
+    let output = Command::new("sh")
+        .arg(script(name))
+        .args(args)
+        .env("API_BASE", server.api_base())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{} failed: {}",
+        name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn run_json(server: &TestServer, name: &str, args: &[&str]) -> Value {
+    let stdout = run(server, name, args);
+    serde_json::from_str(&stdout)
+        .unwrap_or_else(|e| panic!("{} returned invalid JSON: {}", name, e))
+}
+
+#[test]
+fn example_scripts_match_api() {
+    if Command::new("curl").arg("--version").output().is_err() {
+        eprintln!("Skipping example script test: 'curl' not found");
+        return;
+    }
+
+    let server = TestServer::start();
+
+    let created = run_json(&server, "create.sh", &["Example todo"]);
+    assert_eq!(created["title"], "Example todo");
+    assert_eq!(created["priority"], "High");
+    let id = created["id"].as_str().unwrap().to_string();
+
+    let listed = run_json(&server, "list.sh", &[]);
+    assert!(listed.as_array().unwrap().iter().any(|t| t["id"] == id));
+
+    let fetched = run_json(&server, "get.sh", &[&id]);
+    assert_eq!(fetched, created);
+
+    let updated = run_json(&server, "update.sh", &[&id]);
+    assert_eq!(updated["completed"], true);
+
+    let metadata = run_json(&server, "get_metadata.sh", &[&id]);
+    assert_eq!(metadata, serde_json::json!({ "source": "curl" }));
+
+    let patched = run_json(&server, "patch_metadata.sh", &[&id]);
+    assert_eq!(patched, serde_json::json!({ "estimate": "2h" }));
+
+    assert!(run(&server, "delete.sh", &[&id]).is_empty());
+}